@@ -0,0 +1,24 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset name="animated" tilewidth="32" tileheight="32" spacing="1" margin="1" tilecount="4">
+ <image source="animated.png" width="67" height="67" trans="ff00ff"/>
+ <tile id="0">
+  <properties>
+   <property name="solid" type="bool" value="true"/>
+  </properties>
+  <objectgroup>
+   <object id="1" x="0" y="16" width="32" height="16"/>
+   <object id="2" x="4" y="4">
+    <polygon points="0,0 8,0 8,8"/>
+   </object>
+   <object id="3" x="24" y="0" width="8" height="4" rotation="90"/>
+  </objectgroup>
+  <animation>
+   <frame tileid="0" duration="100"/>
+   <frame tileid="1" duration="150"/>
+   <frame tileid="2" duration="100"/>
+  </animation>
+ </tile>
+ <tile id="3">
+  <image source="overlay.png" width="32" height="32"/>
+ </tile>
+</tileset>
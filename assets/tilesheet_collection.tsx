@@ -0,0 +1,13 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset name="props" tilewidth="64" tileheight="64" tilecount="3" columns="0">
+ <grid orientation="orthogonal" width="1" height="1"/>
+ <tile id="0">
+  <image source="barrel.png" width="32" height="48"/>
+ </tile>
+ <tile id="4">
+  <image source="tree.png" width="64" height="64"/>
+ </tile>
+ <tile id="9" x="16" y="8" width="32" height="24">
+  <image source="crates.png" width="96" height="64"/>
+ </tile>
+</tileset>
@@ -0,0 +1,9 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset name="tall" tilewidth="32" tileheight="64" tilecount="12" columns="7" objectalignment="bottom" tilerendersize="grid" fillmode="preserve-aspect-fit">
+ <tileoffset x="0" y="16"/>
+ <grid orientation="isometric" width="32" height="16"/>
+ <properties>
+  <property name="biome" value="forest"/>
+ </properties>
+ <image source="tilesheet.png" width="448" height="192"/>
+</tileset>
@@ -0,0 +1,14 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset name="terrain" tilewidth="32" tileheight="32" tilecount="84" columns="14">
+ <image source="tilesheet.png" width="448" height="192"/>
+ <terraintypes>
+  <terrain name="Grass" tile="0">
+   <properties>
+    <property name="walkable" type="bool" value="true"/>
+   </properties>
+  </terrain>
+  <terrain name="Water" tile="-1"/>
+ </terraintypes>
+ <tile id="0" terrain="0,0,0,0"/>
+ <tile id="1" terrain="0,0,,1"/>
+</tileset>
@@ -0,0 +1,61 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset name="wang" tilewidth="32" tileheight="32" tilecount="84" columns="14">
+ <image source="tilesheet.png" width="448" height="192"/>
+ <tile id="42" type="grass" probability="0.25"/>
+ <wangsets>
+  <wangset name="Ground" type="corner" tile="0">
+   <properties>
+    <property name="season" value="summer"/>
+   </properties>
+   <wangcolor name="Grass" color="#00ff00" tile="0" probability="1">
+    <properties>
+     <property name="walkable" type="bool" value="true"/>
+    </properties>
+   </wangcolor>
+   <wangcolor name="Sand" color="#ffff00" tile="15" probability="0.5"/>
+   <wangtile tileid="0" wangid="0,1,0,1,0,1,0,1"/>
+   <wangtile tileid="1" wangid="0,2,0,1,0,1,0,1"/>
+   <wangtile tileid="2" wangid="0,1,0,2,0,1,0,1"/>
+   <wangtile tileid="3" wangid="0,2,0,2,0,1,0,1"/>
+   <wangtile tileid="4" wangid="0,1,0,1,0,2,0,1"/>
+   <wangtile tileid="5" wangid="0,2,0,1,0,2,0,1"/>
+   <wangtile tileid="6" wangid="0,1,0,2,0,2,0,1"/>
+   <wangtile tileid="7" wangid="0,2,0,2,0,2,0,1"/>
+   <wangtile tileid="8" wangid="0,1,0,1,0,1,0,2"/>
+   <wangtile tileid="9" wangid="0,2,0,1,0,1,0,2"/>
+   <wangtile tileid="10" wangid="0,1,0,2,0,1,0,2"/>
+   <wangtile tileid="11" wangid="0,2,0,2,0,1,0,2"/>
+   <wangtile tileid="12" wangid="0,1,0,1,0,2,0,2"/>
+   <wangtile tileid="13" wangid="0,2,0,1,0,2,0,2"/>
+   <wangtile tileid="14" wangid="0,1,0,2,0,2,0,2"/>
+   <wangtile tileid="15" wangid="0,2,0,2,0,2,0,2"/>
+   <wangtile tileid="42" wangid="0,1,0,1,0,1,0,1"/>
+  </wangset>
+  <wangset name="Roads" type="edge" tile="-1">
+   <wangcolor name="Road" color="#808080" tile="31" probability="1"/>
+   <wangcolor name="Verge" color="#008000" tile="16" probability="1"/>
+   <wangtile tileid="16" wangid="1,0,1,0,1,0,1,0"/>
+   <wangtile tileid="17" wangid="2,0,1,0,1,0,1,0"/>
+   <wangtile tileid="18" wangid="1,0,2,0,1,0,1,0"/>
+   <wangtile tileid="19" wangid="2,0,2,0,1,0,1,0"/>
+   <wangtile tileid="20" wangid="1,0,1,0,2,0,1,0"/>
+   <wangtile tileid="21" wangid="2,0,1,0,2,0,1,0"/>
+   <wangtile tileid="22" wangid="1,0,2,0,2,0,1,0"/>
+   <wangtile tileid="23" wangid="2,0,2,0,2,0,1,0"/>
+   <wangtile tileid="24" wangid="1,0,1,0,1,0,2,0"/>
+   <wangtile tileid="25" wangid="2,0,1,0,1,0,2,0"/>
+   <wangtile tileid="26" wangid="1,0,2,0,1,0,2,0"/>
+   <wangtile tileid="27" wangid="2,0,2,0,1,0,2,0"/>
+   <wangtile tileid="28" wangid="1,0,1,0,2,0,2,0"/>
+   <wangtile tileid="29" wangid="2,0,1,0,2,0,2,0"/>
+   <wangtile tileid="30" wangid="1,0,2,0,2,0,2,0"/>
+   <wangtile tileid="31" wangid="2,0,2,0,2,0,2,0"/>
+  </wangset>
+  <wangset name="Cliffs" type="mixed" tile="32">
+   <wangcolor name="High" color="#ff0000" tile="32" probability="1"/>
+   <wangcolor name="Low" color="#0000ff" tile="-1" probability="1"/>
+   <wangtile tileid="32" wangid="1,1,1,1,1,1,1,1"/>
+   <wangtile tileid="33" wangid="1,1,2,2,2,2,2,1"/>
+  </wangset>
+ </wangsets>
+</tileset>
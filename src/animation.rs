@@ -0,0 +1,53 @@
+// Stepping tile animations, so renderers know which frame of each animated
+// tile to draw.
+
+use std::collections::HashMap;
+
+use Tileset;
+
+/// Keeps the time for tile animations. All tiles start their animations
+/// together when the clock starts, like in Tiled.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub struct AnimationClock {
+    elapsed: u64,
+}
+
+impl AnimationClock {
+    pub fn new() -> AnimationClock {
+        AnimationClock {elapsed: 0}
+    }
+
+    /// A clock which has already run for `elapsed` milliseconds.
+    pub fn at(elapsed: u64) -> AnimationClock {
+        AnimationClock {elapsed}
+    }
+
+    /// Moves the clock forward by `delta` milliseconds.
+    pub fn advance(&mut self, delta: u64) {
+        self.elapsed = self.elapsed.saturating_add(delta);
+    }
+
+    pub fn reset(&mut self) {
+        self.elapsed = 0;
+    }
+
+    /// The milliseconds since the clock started.
+    pub fn elapsed(&self) -> u64 {
+        self.elapsed
+    }
+
+    /// The id of the tile to draw in place of the tile `id` of `tileset`.
+    /// This is the current frame for animated tiles and `id` itself for
+    /// every other tile.
+    pub fn tile_id(&self, tileset: &Tileset, id: u32) -> u32 {
+        tileset.get_tile(id).and_then(|tile| tile.animation_frame(self.elapsed)).unwrap_or(id)
+    }
+
+    /// The current frame of every animated tile of `tileset`, as a map from
+    /// the id of the animated tile to the id of the tile to draw.
+    pub fn tile_ids(&self, tileset: &Tileset) -> HashMap<u32, u32> {
+        tileset.tiles.iter()
+            .filter_map(|tile| tile.animation_frame(self.elapsed).map(|frame| (tile.id, frame)))
+            .collect()
+    }
+}
@@ -0,0 +1,657 @@
+// Loading of maps and tilesets saved in Tiled's JSON format (.tmj and .tsj).
+//
+// This builds exactly the same values as the XML parser, so everything after
+// loading does not need to care which format a map came from.
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use base64::u8de as decode_base64;
+use serde_json::{self, Value};
+
+use {Chunk, Colour, FillMode, Frame, Grid, Group, HorizontalAlignment, Image, ImageLayer, Layer, LayerTile, Loader, Map,
+     MapTileset, Object, ObjectAlignment, ObjectGroup, ObjectShape, Orientation, Properties, PropertyValue, RenderOrder,
+     ResourceReader, Template, Terrain, Tile, TiledError, TileRenderSize, Tileset, VerticalAlignment, WangColour, WangId,
+     WangSet, WangSetType, WangTile};
+use {convert_to_u32, decode_gzip, decode_zlib, no_templates, LayerAttributes, ObjectAttributes, TemplateResolver};
+#[cfg(feature = "zstd")]
+use decode_zstd;
+
+fn malformed(message: &str) -> TiledError {
+    TiledError::MalformedAttributes(message.to_string())
+}
+
+fn string(value: &Value, key: &str) -> Option<String> {
+    value.get(key).and_then(Value::as_str).map(str::to_string)
+}
+
+fn uint(value: &Value, key: &str) -> Option<u32> {
+    value.get(key).and_then(Value::as_u64).map(|v| v as u32)
+}
+
+fn int(value: &Value, key: &str) -> Option<i32> {
+    value.get(key).and_then(Value::as_i64).map(|v| v as i32)
+}
+
+fn float(value: &Value, key: &str) -> Option<f32> {
+    value.get(key).and_then(Value::as_f64).map(|v| v as f32)
+}
+
+fn boolean(value: &Value, key: &str) -> Option<bool> {
+    value.get(key).and_then(Value::as_bool)
+}
+
+fn colour(value: &Value, key: &str) -> Option<Colour> {
+    value.get(key).and_then(Value::as_str).and_then(|v| v.parse().ok())
+}
+
+// Parses a string value into one of the enums used for attributes.
+fn parsed<T: FromStr>(value: &Value, key: &str) -> Result<Option<T>, TiledError> {
+    match value.get(key).and_then(Value::as_str) {
+        Some(v) => v.parse().map(Some).map_err(|_| TiledError::MalformedAttributes(format!("unknown {} \"{}\"", key, v))),
+        None => Ok(None),
+    }
+}
+
+fn array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value.get(key).and_then(Value::as_array).map_or(&[], |v| &v[..])
+}
+
+pub fn parse_map<R: Read, RR: ResourceReader>(reader: R, map_path: Option<&Path>, loader: &mut Loader<RR>) -> Result<Map, TiledError> {
+    let value: Value = serde_json::from_reader(reader).map_err(TiledError::JsonDecodingError)?;
+    map_from_value(&value, map_path, loader)
+}
+
+pub fn parse_template<R: Read, RR: ResourceReader>(reader: R, path: &Path, loader: &mut Loader<RR>) -> Result<Template, TiledError> {
+    let value: Value = serde_json::from_reader(reader).map_err(TiledError::JsonDecodingError)?;
+    let tileset = match value.get("tileset") {
+        Some(tileset) => {
+            match (uint(tileset, "firstgid"), string(tileset, "source")) {
+                (Some(first_gid), Some(source)) => Some(loader.map_tileset(path, &source, first_gid)?),
+                _ => return Err(malformed("template tilesets must have a firstgid and a source")),
+            }
+        }
+        None => None,
+    };
+    let object = value.get("object").ok_or_else(|| TiledError::Other(format!("Template {:?} has no object", path)))?;
+    Ok(Template {source: path.to_path_buf(), tileset, object: object_from_value(object, &mut no_templates)?})
+}
+
+pub fn parse_tileset<R: Read>(reader: R) -> Result<Tileset, TiledError> {
+    let value: Value = serde_json::from_reader(reader).map_err(TiledError::JsonDecodingError)?;
+    tileset_from_value(&value)
+}
+
+// Reads the classes declared in a `.tiled-project` file, with the default
+// values of their members. Enums need no declaration, as their values are
+// read as plain strings and ints.
+pub fn parse_property_types<R: Read>(reader: R) -> Result<HashMap<String, Properties>, TiledError> {
+    let value: Value = serde_json::from_reader(reader).map_err(TiledError::JsonDecodingError)?;
+    let mut classes = HashMap::new();
+    for class in array(&value, "propertyTypes") {
+        if string(class, "type").as_deref() != Some("class") {
+            continue;
+        }
+        let name = string(class, "name").ok_or_else(|| malformed("property types must have a name"))?;
+        let mut members = HashMap::new();
+        for member in array(class, "members") {
+            let member_name = string(member, "name").ok_or_else(|| malformed("class members must have a name"))?;
+            let member_type = string(member, "type").unwrap_or_else(|| "string".to_string());
+            let value = match member.get("value") {
+                Some(value) if member_type != "class" => property_value(member_type, value)?,
+                value => PropertyValue::ClassValue {
+                    property_type: string(member, "propertyType").unwrap_or_default(),
+                    properties: class_members(value)?,
+                },
+            };
+            members.insert(member_name, value);
+        }
+        classes.insert(name, members);
+    }
+    Ok(classes)
+}
+
+fn map_from_value<RR: ResourceReader>(value: &Value, map_path: Option<&Path>, loader: &mut Loader<RR>) -> Result<Map, TiledError> {
+    // Tiled wrote the version as a number before 1.6.
+    let version = match value.get("version") {
+        Some(Value::String(v)) => Some(v.clone()),
+        Some(Value::Number(v)) => Some(v.to_string()),
+        _ => None,
+    };
+    let orientation = string(value, "orientation").and_then(|v| v.parse().ok());
+    let (version, orientation, width, height, tile_width, tile_height) =
+        match (version, orientation, uint(value, "width"), uint(value, "height"),
+               uint(value, "tilewidth"), uint(value, "tileheight")) {
+            (Some(v), Some(o), Some(w), Some(h), Some(tw), Some(th)) => (v, o, w, h, tw, th),
+            _ => return Err(malformed("map must have a version, width and height with correct types")),
+        };
+    let infinite = boolean(value, "infinite").unwrap_or(false);
+
+    let mut tilesets = Vec::new();
+    for tileset in array(value, "tilesets") {
+        let first_gid = uint(tileset, "firstgid")
+            .ok_or_else(|| malformed("tileset must have a firstgid, name tile width and height with correct types"))?;
+        match string(tileset, "source") {
+            Some(source) => {
+                let map_path = map_path.ok_or_else(|| TiledError::Other("Maps with external tilesets must know their file location.  See parse_json_file(Path).".to_string()))?;
+                tilesets.push(loader.map_tileset(map_path, &source, first_gid)?);
+            }
+            None => {
+                let tileset = Arc::new(tileset_from_value(tileset)?);
+                tilesets.push(MapTileset {first_gid, tileset, source: None});
+            }
+        }
+    }
+
+    let mut layers = LayerLists::default();
+    {
+        let mut templates = |source: &str| loader.object_template(map_path, source, &tilesets);
+        layers.parse(array(value, "layers"), infinite, &mut templates)?;
+    }
+
+    Ok(Map {
+        version,
+        tiled_version: string(value, "tiledversion"),
+        orientation,
+        render_order: parsed(value, "renderorder")?.unwrap_or(RenderOrder::RightDown),
+        compression_level: int(value, "compressionlevel").unwrap_or(-1),
+        width,
+        height,
+        tile_width,
+        tile_height,
+        hex_side_length: uint(value, "hexsidelength"),
+        stagger_axis: parsed(value, "staggeraxis")?,
+        stagger_index: parsed(value, "staggerindex")?,
+        parallax_origin_x: float(value, "parallaxoriginx").unwrap_or(0.0),
+        parallax_origin_y: float(value, "parallaxoriginy").unwrap_or(0.0),
+        tilesets,
+        layers: layers.layers,
+        image_layers: layers.image_layers,
+        object_groups: layers.object_groups,
+        groups: layers.groups,
+        properties: properties(value)?,
+        background_colour: colour(value, "backgroundcolor"),
+        infinite,
+        next_layer_id: uint(value, "nextlayerid"),
+        next_object_id: uint(value, "nextobjectid"),
+    })
+}
+
+// JSON keeps all kinds of layers in a single array, so they are sorted into
+// the separate lists used by `Map` and `Group` here.
+#[derive(Default)]
+struct LayerLists {
+    layers: Vec<Layer>,
+    image_layers: Vec<ImageLayer>,
+    object_groups: Vec<ObjectGroup>,
+    groups: Vec<Group>,
+}
+
+impl LayerLists {
+    fn parse(&mut self, values: &[Value], infinite: bool, templates: &mut TemplateResolver) -> Result<(), TiledError> {
+        for (layer_index, value) in values.iter().enumerate() {
+            let layer_index = layer_index as u32;
+            match value.get("type").and_then(Value::as_str) {
+                Some("tilelayer") => self.layers.push(layer_from_value(value, infinite, layer_index)?),
+                Some("imagelayer") => self.image_layers.push(image_layer_from_value(value, layer_index)?),
+                Some("objectgroup") => self.object_groups.push(object_group_from_value(value, Some(layer_index), templates)?),
+                Some("group") => self.groups.push(group_from_value(value, infinite, layer_index, templates)?),
+                Some(t) => return Err(TiledError::Other(format!("Unknown layer type \"{}\"", t))),
+                None => return Err(malformed("layer must have a type")),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn properties(value: &Value) -> Result<Properties, TiledError> {
+    let mut properties = HashMap::new();
+    match value.get("properties") {
+        Some(Value::Array(list)) => {
+            for property in list {
+                let name = string(property, "name")
+                    .ok_or_else(|| malformed("property must have a name and a value"))?;
+                let property_type = string(property, "type").unwrap_or_else(|| "string".to_string());
+                if property_type == "class" {
+                    let class = PropertyValue::ClassValue {
+                        property_type: string(property, "propertytype").unwrap_or_default(),
+                        properties: class_members(property.get("value"))?,
+                    };
+                    properties.insert(name, class);
+                    continue;
+                }
+                let value = property.get("value")
+                    .ok_or_else(|| malformed("property must have a name and a value"))?;
+                properties.insert(name, property_value(property_type, value)?);
+            }
+        }
+        // Before Tiled 1.2 properties were an object, with their types kept in
+        // a separate `propertytypes` object.
+        Some(Value::Object(map)) => {
+            for (name, property) in map {
+                let property_type = value.get("propertytypes")
+                    .and_then(|types| string(types, name))
+                    .unwrap_or_else(|| "string".to_string());
+                properties.insert(name.clone(), property_value(property_type, property)?);
+            }
+        }
+        _ => {}
+    }
+    Ok(properties)
+}
+
+fn property_value(property_type: String, value: &Value) -> Result<PropertyValue, TiledError> {
+    let value = match *value {
+        Value::String(ref s) => s.clone(),
+        ref other => other.to_string(),
+    };
+    PropertyValue::new(property_type, value)
+}
+
+// The members of a class property are stored as a plain object without their
+// types, so the types are guessed from the JSON values. `PropertyTypes`
+// corrects the guesses using the declared classes.
+fn class_members(value: Option<&Value>) -> Result<Properties, TiledError> {
+    let mut members = HashMap::new();
+    let object = match value {
+        Some(Value::Object(object)) => object,
+        None => return Ok(members),
+        Some(_) => return Err(malformed("class property values must be objects")),
+    };
+    for (name, member) in object {
+        let value = match *member {
+            Value::Bool(b) => PropertyValue::BoolValue(b),
+            Value::Number(ref n) => match n.as_i64() {
+                Some(i) if i >= i64::from(i32::MIN) && i <= i64::from(i32::MAX) => PropertyValue::IntValue(i as i32),
+                _ => PropertyValue::FloatValue(n.as_f64().unwrap_or(0.0) as f32),
+            },
+            Value::String(ref s) => PropertyValue::StringValue(s.clone()),
+            Value::Object(_) => PropertyValue::ClassValue {property_type: String::new(), properties: class_members(Some(member))?},
+            _ => return Err(malformed("class members must be a bool, number, string or object")),
+        };
+        members.insert(name.clone(), value);
+    }
+    Ok(members)
+}
+
+fn tileset_from_value(value: &Value) -> Result<Tileset, TiledError> {
+    let (name, tile_width, tile_height) =
+        match (string(value, "name"), uint(value, "tilewidth"), uint(value, "tileheight")) {
+            (Some(n), Some(w), Some(h)) => (n, w, h),
+            _ => return Err(malformed("tileset must have a firstgid, name tile width and height with correct types")),
+        };
+    let mut images = Vec::new();
+    if let Some(image) = image_from_value(value)? {
+        images.push(image);
+    }
+    let tiles = array(value, "tiles").iter().map(tile_from_value).collect::<Result<_, _>>()?;
+    let grid = match value.get("grid") {
+        Some(grid) => match (uint(grid, "width"), uint(grid, "height")) {
+            (Some(width), Some(height)) => Some(Grid {
+                orientation: parsed(grid, "orientation")?.unwrap_or(Orientation::Orthogonal),
+                width,
+                height,
+            }),
+            _ => return Err(malformed("grid must have a width and height with correct types")),
+        },
+        None => None,
+    };
+    let tile_offset = value.get("tileoffset");
+    Ok(Tileset {
+        name,
+        tile_width,
+        tile_height,
+        spacing: uint(value, "spacing").unwrap_or(0),
+        margin: uint(value, "margin").unwrap_or(0),
+        tile_count: uint(value, "tilecount"),
+        columns: uint(value, "columns"),
+        object_alignment: parsed(value, "objectalignment")?.unwrap_or(ObjectAlignment::Unspecified),
+        tile_offset_x: tile_offset.and_then(|o| int(o, "x")).unwrap_or(0),
+        tile_offset_y: tile_offset.and_then(|o| int(o, "y")).unwrap_or(0),
+        grid,
+        tile_render_size: parsed(value, "tilerendersize")?.unwrap_or(TileRenderSize::Tile),
+        fill_mode: parsed(value, "fillmode")?.unwrap_or(FillMode::Stretch),
+        properties: properties(value)?,
+        images,
+        tiles,
+        terrains: array(value, "terrains").iter().map(terrain_from_value).collect::<Result<_, _>>()?,
+        wang_sets: array(value, "wangsets").iter().map(wang_set_from_value).collect::<Result<_, _>>()?,
+    })
+}
+
+fn tile_from_value(value: &Value) -> Result<Tile, TiledError> {
+    let id = uint(value, "id").ok_or_else(|| malformed("tile must have an id with the correct type"))?;
+    let mut images = Vec::new();
+    if let Some(image) = image_from_value(value)? {
+        images.push(image);
+    }
+    let terrain = match value.get("terrain").and_then(Value::as_array) {
+        Some(corners) if corners.len() == 4 => {
+            let mut terrain = [None; 4];
+            for (corner, index) in terrain.iter_mut().zip(corners) {
+                *corner = index.as_i64().filter(|&i| i >= 0).map(|i| i as u32);
+            }
+            Some(terrain)
+        }
+        Some(_) => return Err(malformed("tile terrain must have four corners")),
+        None => None,
+    };
+    let objectgroup = match value.get("objectgroup") {
+        Some(group) => Some(object_group_from_value(group, None, &mut no_templates)?),
+        None => None,
+    };
+    let animation = match value.get("animation").and_then(Value::as_array) {
+        Some(frames) => Some(frames.iter().map(|frame| {
+            match (uint(frame, "tileid"), uint(frame, "duration")) {
+                (Some(tile_id), Some(duration)) => Ok(Frame {tile_id, duration}),
+                _ => Err(malformed("A frame must have tileid and duration")),
+            }
+        }).collect::<Result<_, _>>()?),
+        None => None,
+    };
+    Ok(Tile {
+        id,
+        tile_type: string(value, "class").or_else(|| string(value, "type")).unwrap_or_default(),
+        probability: float(value, "probability").unwrap_or(1.0),
+        images,
+        x: uint(value, "x").unwrap_or(0),
+        y: uint(value, "y").unwrap_or(0),
+        width: uint(value, "width"),
+        height: uint(value, "height"),
+        terrain,
+        properties: properties(value)?,
+        objectgroup,
+        animation,
+    })
+}
+
+// Terrains and Wang sets and colours use -1 for a missing tile.
+fn tile_id(value: &Value, key: &str) -> Option<u32> {
+    value.get(key).and_then(Value::as_i64).filter(|&tile| tile >= 0).map(|tile| tile as u32)
+}
+
+fn terrain_from_value(value: &Value) -> Result<Terrain, TiledError> {
+    Ok(Terrain {
+        name: string(value, "name").ok_or_else(|| malformed("terrain must have a name"))?,
+        tile: tile_id(value, "tile"),
+        properties: properties(value)?,
+    })
+}
+
+fn wang_set_from_value(value: &Value) -> Result<WangSet, TiledError> {
+    let colours = array(value, "colors").iter().map(|wang_colour| {
+        match (string(wang_colour, "name"), colour(wang_colour, "color")) {
+            (Some(name), Some(c)) => Ok(WangColour {
+                name,
+                colour: c,
+                tile: tile_id(wang_colour, "tile"),
+                probability: float(wang_colour, "probability").unwrap_or(1.0),
+                properties: properties(wang_colour)?,
+            }),
+            _ => Err(malformed("wang colour must have a name and a colour")),
+        }
+    }).collect::<Result<_, _>>()?;
+    let wang_tiles = array(value, "wangtiles").iter().map(|tile| {
+        let ids = array(tile, "wangid");
+        match (uint(tile, "tileid"), ids.len()) {
+            (Some(tile_id), 8) => {
+                let mut wang_id = WangId::default();
+                for (colour, id) in wang_id.0.iter_mut().zip(ids) {
+                    *colour = id.as_u64().ok_or_else(|| malformed("wang tile must have a tileid and a wangid"))? as u8;
+                }
+                Ok(WangTile {tile_id, wang_id})
+            }
+            _ => Err(malformed("wang tile must have a tileid and a wangid")),
+        }
+    }).collect::<Result<_, _>>()?;
+    Ok(WangSet {
+        name: string(value, "name").ok_or_else(|| malformed("wang set must have a name"))?,
+        set_type: parsed(value, "type")?.unwrap_or(WangSetType::Mixed),
+        tile: tile_id(value, "tile"),
+        colours,
+        wang_tiles,
+        properties: properties(value)?,
+    })
+}
+
+// Tilesets, tiles and image layers all keep their image in the `image`,
+// `imagewidth`, `imageheight` and `transparentcolor` fields. An empty source
+// means there is no image.
+fn image_from_value(value: &Value) -> Result<Option<Image>, TiledError> {
+    let source = match string(value, "image") {
+        Some(ref s) if s.is_empty() => return Ok(None),
+        Some(s) => s,
+        None => return Ok(None),
+    };
+    Ok(Some(Image {
+        source,
+        width: int(value, "imagewidth"),
+        height: int(value, "imageheight"),
+        transparent_colour: colour(value, "transparentcolor"),
+        format: None,
+        data: None,
+    }))
+}
+
+fn layer_attributes(value: &Value) -> LayerAttributes {
+    LayerAttributes {
+        id: uint(value, "id").unwrap_or(0),
+        locked: boolean(value, "locked").unwrap_or(false),
+        tint_colour: colour(value, "tintcolor"),
+        offset_x: float(value, "offsetx").unwrap_or(0.0),
+        offset_y: float(value, "offsety").unwrap_or(0.0),
+        parallax_x: float(value, "parallaxx").unwrap_or(1.0),
+        parallax_y: float(value, "parallaxy").unwrap_or(1.0),
+    }
+}
+
+fn layer_from_value(value: &Value, infinite: bool, layer_index: u32) -> Result<Layer, TiledError> {
+    let name = string(value, "name").ok_or_else(|| malformed("layer must have a name"))?;
+    let encoding = string(value, "encoding");
+    let compression = string(value, "compression");
+    let mut tiles = Vec::new();
+    let mut chunks = Vec::new();
+    if infinite {
+        for chunk in array(value, "chunks") {
+            let (x, y, width, height) = match (int(chunk, "x"), int(chunk, "y"), uint(chunk, "width"), uint(chunk, "height")) {
+                (Some(x), Some(y), Some(w), Some(h)) => (x, y, w, h),
+                _ => return Err(malformed("chunk must have an x, y, width and height with correct types")),
+            };
+            let tiles = decode_data(chunk, encoding.as_ref(), compression.as_ref(), width, height)?;
+            chunks.push(Chunk {x, y, width, height, tiles});
+        }
+    } else {
+        let width = uint(value, "width").ok_or_else(|| malformed("layer must have a width"))?;
+        let height = uint(value, "height").ok_or_else(|| malformed("layer must have a height"))?;
+        tiles = decode_data(value, encoding.as_ref(), compression.as_ref(), width, height)?;
+    }
+    let common = layer_attributes(value);
+    Ok(Layer {
+        id: common.id,
+        name,
+        opacity: float(value, "opacity").unwrap_or(1.0),
+        visible: boolean(value, "visible").unwrap_or(true),
+        locked: common.locked,
+        tint_colour: common.tint_colour,
+        offset_x: common.offset_x,
+        offset_y: common.offset_y,
+        parallax_x: common.parallax_x,
+        parallax_y: common.parallax_y,
+        tiles,
+        chunks,
+        properties: properties(value)?,
+        layer_index,
+    })
+}
+
+// Decodes the `data` field of a tile layer or chunk, which is either an array
+// of gids or a base64 string.
+fn decode_data(value: &Value, encoding: Option<&String>, compression: Option<&String>, width: u32, height: u32) -> Result<Vec<Vec<LayerTile>>, TiledError> {
+    let gids = match (value.get("data"), encoding.map(String::as_str)) {
+        (Some(Value::Array(gids)), None) | (Some(Value::Array(gids)), Some("csv")) => {
+            let gids = gids.iter().map(|v| v.as_u64().map(|v| v as u32))
+                .collect::<Option<Vec<u32>>>()
+                .ok_or_else(|| malformed("tile data must only contain gids"))?;
+            if width == 0 {
+                Vec::new()
+            } else {
+                gids.chunks(width as usize).map(|row| row.to_vec()).collect()
+            }
+        }
+        (Some(Value::String(data)), Some("base64")) => {
+            let bytes = decode_base64(data.trim().as_bytes()).map_err(TiledError::Base64DecodingError)?;
+            let bytes = match compression.map(String::as_str) {
+                None | Some("") => bytes,
+                Some("zlib") => decode_zlib(bytes)?,
+                Some("gzip") => decode_gzip(bytes)?,
+                #[cfg(feature = "zstd")]
+                Some("zstd") => decode_zstd(bytes)?,
+                #[cfg(not(feature = "zstd"))]
+                Some("zstd") => return Err(TiledError::Other("zstd compression requires the \"zstd\" feature".to_string())),
+                Some(c) => return Err(TiledError::Other(format!("Unknown combination of base64 encoding and {} compression", c))),
+            };
+            if width == 0 || bytes.len() as u64 != width as u64 * height as u64 * 4 {
+                return Err(TiledError::Other(format!("Tile data has {} bytes, but a {}x{} layer needs {}",
+                                                     bytes.len(), width, height, width as u64 * height as u64 * 4)));
+            }
+            convert_to_u32(&bytes, width)?
+        }
+        (None, _) => Vec::new(),
+        (_, Some(e)) => return Err(TiledError::Other(format!("Unknown encoding format {}", e))),
+        (_, None) => return Err(TiledError::Other("Missing encoding format".to_string())),
+    };
+    Ok(gids.into_iter().map(|row| row.into_iter().map(LayerTile::new).collect()).collect())
+}
+
+fn image_layer_from_value(value: &Value, layer_index: u32) -> Result<ImageLayer, TiledError> {
+    let name = string(value, "name").ok_or_else(|| malformed("layer must have a name"))?;
+    let common = layer_attributes(value);
+    Ok(ImageLayer {
+        id: common.id,
+        name,
+        opacity: float(value, "opacity").unwrap_or(1.0),
+        visible: boolean(value, "visible").unwrap_or(true),
+        locked: common.locked,
+        tint_colour: common.tint_colour,
+        offset_x: common.offset_x,
+        offset_y: common.offset_y,
+        parallax_x: common.parallax_x,
+        parallax_y: common.parallax_y,
+        image: image_from_value(value)?,
+        repeat_x: boolean(value, "repeatx").unwrap_or(false),
+        repeat_y: boolean(value, "repeaty").unwrap_or(false),
+        properties: properties(value)?,
+        layer_index,
+    })
+}
+
+fn group_from_value(value: &Value, infinite: bool, layer_index: u32, templates: &mut TemplateResolver) -> Result<Group, TiledError> {
+    let mut layers = LayerLists::default();
+    layers.parse(array(value, "layers"), infinite, templates)?;
+    let common = layer_attributes(value);
+    Ok(Group {
+        id: common.id,
+        name: string(value, "name").unwrap_or_default(),
+        opacity: float(value, "opacity").unwrap_or(1.0),
+        visible: boolean(value, "visible").unwrap_or(true),
+        locked: common.locked,
+        tint_colour: common.tint_colour,
+        offset_x: common.offset_x,
+        offset_y: common.offset_y,
+        parallax_x: common.parallax_x,
+        parallax_y: common.parallax_y,
+        layers: layers.layers,
+        image_layers: layers.image_layers,
+        object_groups: layers.object_groups,
+        groups: layers.groups,
+        properties: properties(value)?,
+        layer_index,
+    })
+}
+
+fn object_group_from_value(value: &Value, layer_index: Option<u32>, templates: &mut TemplateResolver) -> Result<ObjectGroup, TiledError> {
+    let objects = array(value, "objects").iter().map(|object| object_from_value(object, templates)).collect::<Result<_, _>>()?;
+    let common = layer_attributes(value);
+    Ok(ObjectGroup {
+        id: common.id,
+        name: string(value, "name").unwrap_or_default(),
+        opacity: float(value, "opacity").unwrap_or(1.0),
+        visible: boolean(value, "visible").unwrap_or(true),
+        locked: common.locked,
+        tint_colour: common.tint_colour,
+        offset_x: common.offset_x,
+        offset_y: common.offset_y,
+        parallax_x: common.parallax_x,
+        parallax_y: common.parallax_y,
+        objects,
+        colour: colour(value, "color"),
+        layer_index,
+    })
+}
+
+fn object_from_value(value: &Value, templates: &mut TemplateResolver) -> Result<Object, TiledError> {
+    let shape = if boolean(value, "ellipse").unwrap_or(false) {
+        Some(ObjectShape::Ellipse {width: 0.0, height: 0.0})
+    } else if let Some(points) = value.get("polyline") {
+        Some(ObjectShape::Polyline {points: points_from_value(points)?})
+    } else if let Some(points) = value.get("polygon") {
+        Some(ObjectShape::Polygon {points: points_from_value(points)?})
+    } else if boolean(value, "point").unwrap_or(false) {
+        Some(ObjectShape::Point)
+    } else if let Some(text) = value.get("text") {
+        Some(text_from_value(text)?)
+    } else {
+        None
+    };
+    let template = match string(value, "template") {
+        Some(source) => Some(templates(&source)?),
+        None => None,
+    };
+    let attrs = ObjectAttributes {
+        id: uint(value, "id"),
+        gid: uint(value, "gid"),
+        name: string(value, "name"),
+        obj_type: string(value, "type"),
+        x: float(value, "x"),
+        y: float(value, "y"),
+        width: float(value, "width"),
+        height: float(value, "height"),
+        rotation: float(value, "rotation"),
+        visible: boolean(value, "visible"),
+        shape,
+        properties: properties(value)?,
+    };
+    Ok(attrs.build(template))
+}
+
+fn text_from_value(value: &Value) -> Result<ObjectShape, TiledError> {
+    Ok(ObjectShape::Text {
+        width: 0.0,
+        height: 0.0,
+        text: string(value, "text").unwrap_or_default(),
+        font_family: string(value, "fontfamily").unwrap_or_else(|| "sans-serif".to_string()),
+        pixel_size: uint(value, "pixelsize").unwrap_or(16),
+        wrap: boolean(value, "wrap").unwrap_or(false),
+        colour: colour(value, "color").unwrap_or(Colour {red: 0, green: 0, blue: 0, alpha: 255}),
+        bold: boolean(value, "bold").unwrap_or(false),
+        italic: boolean(value, "italic").unwrap_or(false),
+        underline: boolean(value, "underline").unwrap_or(false),
+        strikeout: boolean(value, "strikeout").unwrap_or(false),
+        kerning: boolean(value, "kerning").unwrap_or(true),
+        halign: parsed(value, "halign")?.unwrap_or(HorizontalAlignment::Left),
+        valign: parsed(value, "valign")?.unwrap_or(VerticalAlignment::Top),
+    })
+}
+
+fn points_from_value(value: &Value) -> Result<Vec<(f32, f32)>, TiledError> {
+    let points = value.as_array().ok_or_else(|| malformed("A polyline must have points"))?;
+    points.iter().map(|point| {
+        match (float(point, "x"), float(point, "y")) {
+            (Some(x), Some(y)) => Ok((x, y)),
+            _ => Err(malformed("one of a polyline's points does not have an x and y coordinate")),
+        }
+    }).collect()
+}
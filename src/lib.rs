@@ -1,23 +1,51 @@
 extern crate flate2;
 extern crate xml;
 extern crate base64;
+extern crate serde_json;
+#[cfg(feature = "zstd")]
+extern crate zstd;
 
 use std::str::FromStr;
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{BufReader, Read, Error};
-use std::path::Path;
+use std::io::{BufReader, Read, Write, Error};
+use std::path::{Path, PathBuf};
 use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
 use xml::reader::{EventReader, Error as XmlError};
+use xml::writer::Error as XmlWriteError;
 use xml::reader::XmlEvent;
 use xml::attribute::OwnedAttribute;
 use base64::{u8de as decode_base64, Base64Error};
 use flate2::read::{ZlibDecoder, GzDecoder};
 
+mod animation;
+mod json;
+mod loader;
+mod property_types;
+mod resource;
+mod wang;
+mod writer;
+
+pub use animation::AnimationClock;
+pub use loader::Loader;
+pub use property_types::PropertyTypes;
+pub use resource::{FilesystemResourceReader, MemoryResourceReader, ResourceReader};
+pub use wang::{Autotiler, WangRng};
+pub use writer::{DataCompression, DataEncoding, WriteOptions};
+
 #[derive(Debug, Copy, Clone)]
 pub enum ParseTileError {
     ColourError,
     OrientationError,
+    AlignmentError,
+    RenderOrderError,
+    StaggerError,
+    TileRenderSizeError,
+    FillModeError,
+    WangSetTypeError,
+    WangIdError,
 }
 
 // Loops through the attributes once and pulls out the ones we ask it to. It
@@ -80,28 +108,48 @@ macro_rules! parse_tag {
 pub struct Colour {
     pub red: u8,
     pub green: u8,
-    pub blue: u8
+    pub blue: u8,
+    /// 255 unless the colour was given as `#AARRGGBB`.
+    pub alpha: u8,
 }
 
 impl FromStr for Colour {
     type Err = ParseTileError;
 
     fn from_str(s: &str) -> Result<Colour, ParseTileError> {
+        // The digits are sliced out by byte.
+        if !s.is_ascii() {
+            return Err(ParseTileError::ColourError);
+        }
         let s = if s.starts_with("#") {
             &s[1..]
         } else {
             s
         };
-        if s.len() != 6 {
-            return Err(ParseTileError::ColourError);
-        }
+        // Tiled puts the alpha first when there is one.
+        let (a, s) = match s.len() {
+            6 => (Ok(255), s),
+            8 => (u8::from_str_radix(&s[0..2], 16), &s[2..]),
+            _ => return Err(ParseTileError::ColourError),
+        };
         let r = u8::from_str_radix(&s[0..2], 16);
         let g = u8::from_str_radix(&s[2..4], 16);
         let b = u8::from_str_radix(&s[4..6], 16);
-        if r.is_ok() && g.is_ok() && b.is_ok() {
-            return Ok(Colour {red: r.unwrap(), green: g.unwrap(), blue: b.unwrap()})
+        match (r, g, b, a) {
+            (Ok(red), Ok(green), Ok(blue), Ok(alpha)) => Ok(Colour {red, green, blue, alpha}),
+            _ => Err(ParseTileError::ColourError),
+        }
+    }
+}
+
+impl fmt::Display for Colour {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        if self.alpha != 255 {
+            write!(fmt, "#{:02x}", self.alpha)?;
+        } else {
+            fmt.write_str("#")?;
         }
-        Err(ParseTileError::ColourError)
+        write!(fmt, "{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
     }
 }
 
@@ -112,10 +160,14 @@ pub enum TiledError {
     /// correctly.
     MalformedAttributes(String),
     /// An error occured when decompressing using the
-    /// [flate2](https://github.com/alexcrichton/flate2-rs) crate.
+    /// [flate2](https://github.com/alexcrichton/flate2-rs) crate, or the
+    /// [zstd](https://github.com/gyscos/zstd-rs) crate.
     DecompressingError(Error),
     Base64DecodingError(Base64Error),
     XmlDecodingError(XmlError),
+    JsonDecodingError(serde_json::Error),
+    /// An error occured when writing a map or tileset as XML.
+    XmlEncodingError(XmlWriteError),
     PrematureEnd(String),
     Other(String)
 }
@@ -127,6 +179,8 @@ impl fmt::Display for TiledError {
             TiledError::DecompressingError(ref e) => write!(fmt, "{}", e),
             TiledError::Base64DecodingError(ref e) => write!(fmt, "{}", e),
             TiledError::XmlDecodingError(ref e) => write!(fmt, "{}", e),
+            TiledError::JsonDecodingError(ref e) => write!(fmt, "{}", e),
+            TiledError::XmlEncodingError(ref e) => write!(fmt, "{}", e),
             TiledError::PrematureEnd(ref e) => write!(fmt, "{}", e),
             TiledError::Other(ref s) => write!(fmt, "{}", s),
         }
@@ -141,6 +195,8 @@ impl std::error::Error for TiledError {
             TiledError::DecompressingError(ref e) => e.description(),
             TiledError::Base64DecodingError(ref e) => e.description(),
             TiledError::XmlDecodingError(ref e) => e.description(),
+            TiledError::JsonDecodingError(ref e) => e.description(),
+            TiledError::XmlEncodingError(ref e) => e.description(),
             TiledError::PrematureEnd(ref s) => s.as_ref(),
             TiledError::Other(ref s) => s.as_ref(),
         }
@@ -151,6 +207,8 @@ impl std::error::Error for TiledError {
             TiledError::DecompressingError(ref e) => Some(e as &std::error::Error),
             TiledError::Base64DecodingError(ref e) => Some(e as &std::error::Error),
             TiledError::XmlDecodingError(ref e) => Some(e as &std::error::Error),
+            TiledError::JsonDecodingError(ref e) => Some(e as &std::error::Error),
+            TiledError::XmlEncodingError(ref e) => Some(e as &std::error::Error),
             TiledError::PrematureEnd(_) => None,
             TiledError::Other(_) => None,
         }
@@ -165,6 +223,14 @@ pub enum PropertyValue {
     IntValue(i32),
     ColorValue(u32),
     StringValue(String),
+    /// A property of a custom class, holding the values of the class's
+    /// members. Members left at their default are not stored in the file,
+    /// see `PropertyTypes` for filling them in.
+    ClassValue {
+        /// The name of the class.
+        property_type: String,
+        properties: Properties,
+    },
 }
 
 impl PropertyValue {
@@ -202,43 +268,101 @@ fn parse_properties<R: Read>(parser: &mut EventReader<R>) -> Result<Properties,
     parse_tag!(
         parser, "properties",
         "property" => |attrs:Vec<OwnedAttribute>| {
-             let (t, (k, v)) = get_attrs!(
+             let ((t, c, v), k) = get_attrs!(
                  attrs,
-                 optionals: [("type", property_type, |v| Some(v))],
-                 required: [("name", key, |v| Some(v)),
-                            ("value", value, |v| Some(v))],
-                 TiledError::MalformedAttributes("property must have a name and a value".to_string()));
+                 optionals: [("type", property_type, Some),
+                             ("propertytype", class, Some),
+                             ("value", value, Some)],
+                 required: [("name", key, Some)],
+                 TiledError::MalformedAttributes("property must have a name".to_string()));
              let t = t.unwrap_or("string".into());
 
-             p.insert(k, try!(PropertyValue::new(t, v)));
+             if t == "class" {
+                 let properties = parse_class_members(parser)?;
+                 p.insert(k, PropertyValue::ClassValue {property_type: c.unwrap_or_default(), properties});
+             } else {
+                 p.insert(k, PropertyValue::new(t, v.unwrap_or_default())?);
+             }
              Ok(())
         }
     );
     Ok(p)
 }
 
+// Parses the members of a class property, which are nested inside the
+// `<property>` element.
+fn parse_class_members<R: Read>(parser: &mut EventReader<R>) -> Result<Properties, TiledError> {
+    let mut members = HashMap::new();
+    parse_tag!(parser, "property",
+               "properties" => |_| {
+                   members = parse_properties(parser)?;
+                   Ok(())
+               });
+    Ok(members)
+}
+
 /// All Tiled files will be parsed into this. Holds all the layers and tilesets
 #[derive(Debug, PartialEq, Clone)]
 pub struct Map {
     pub version: String,
+    /// The version of Tiled which saved the map.
+    pub tiled_version: Option<String>,
     pub orientation: Orientation,
+    /// The order tiles are drawn in. This only matters when tiles overlap.
+    pub render_order: RenderOrder,
+    /// The compression level used for tile layer data, -1 means the default
+    /// level of the compression algorithm.
+    pub compression_level: i32,
     pub width: u32,
     pub height: u32,
     pub tile_width: u32,
     pub tile_height: u32,
-    pub tilesets: Vec<Tileset>,
+    /// The width or height, depending on `stagger_axis`, of the straight
+    /// edge of hexagonal tiles. Only used by hexagonal maps.
+    pub hex_side_length: Option<u32>,
+    /// Which axis is staggered. Only used by staggered and hexagonal maps.
+    pub stagger_axis: Option<StaggerAxis>,
+    /// Whether the odd or even rows or columns are shifted. Only used by
+    /// staggered and hexagonal maps.
+    pub stagger_index: Option<StaggerIndex>,
+    /// The origin used for the parallax scrolling of layers, relative to the
+    /// top left of the map.
+    pub parallax_origin_x: f32,
+    pub parallax_origin_y: f32,
+    pub tilesets: Vec<MapTileset>,
     pub layers: Vec<Layer>,
     pub image_layers: Vec<ImageLayer>,
     pub object_groups: Vec<ObjectGroup>,
+    pub groups: Vec<Group>,
     pub properties: Properties,
     pub background_colour: Option<Colour>,
+    /// Whether the map is infinite. Tile layers of infinite maps store their
+    /// data in `Layer::chunks` instead of `Layer::tiles`.
+    pub infinite: bool,
+    /// The id the next layer added in Tiled will get.
+    pub next_layer_id: Option<u32>,
+    /// The id the next object added in Tiled will get.
+    pub next_object_id: Option<u32>,
 }
 
 impl Map {
-    fn new<R: Read, P: AsRef<Path>>(parser: &mut EventReader<R>, attrs: Vec<OwnedAttribute>, map_path: Option<P>) -> Result<Map, TiledError>  {
-        let (c, (v, o, w, h, tw, th)) = get_attrs!(
+    fn new<R: Read, RR: ResourceReader>(parser: &mut EventReader<R>, attrs: Vec<OwnedAttribute>, map_path: Option<&Path>,
+                                        loader: &mut Loader<RR>) -> Result<Map, TiledError>  {
+        let ((c, infinite, tiled_version, render_order, compression_level, hex_side_length, stagger_axis, stagger_index,
+              parallax_origin_x, parallax_origin_y, next_layer_id, next_object_id), (v, o, w, h, tw, th)) = get_attrs!(
             attrs,
-            optionals: [("backgroundcolor", colour, |v:String| v.parse().ok())],
+            optionals: [("backgroundcolor", colour, |v:String| v.parse().ok()),
+                        ("infinite", infinite, |v:String| Some(v == "1")),
+                        ("tiledversion", tiled_version, Some),
+                        ("renderorder", render_order, |v:String| v.parse().ok()),
+                        ("compressionlevel", compression_level, |v:String| v.parse().ok()),
+                        ("hexsidelength", hex_side_length, |v:String| v.parse().ok()),
+                        ("staggeraxis", stagger_axis, |v:String| v.parse().ok()),
+                        ("staggerindex", stagger_index, |v:String| v.parse().ok()),
+                        ("parallaxoriginx", parallax_origin_x, |v:String| v.parse().ok()),
+                        ("parallaxoriginy", parallax_origin_y, |v:String| v.parse().ok()),
+                        ("nextlayerid", next_layer_id, |v:String| v.parse().ok()),
+                        ("nextobjectid", next_object_id, |v:String| v.parse().ok())],
             required: [("version", version, |v| Some(v)),
                        ("orientation", orientation, |v:String| v.parse().ok()),
                        ("width", width, |v:String| v.parse().ok()),
@@ -252,17 +376,22 @@ impl Map {
         let mut image_layers = Vec::new();
         let mut properties = HashMap::new();
         let mut object_groups = Vec::new();
+        let mut groups = Vec::new();
+        let mut layer_index = 0;
+        let infinite = infinite.unwrap_or(false);
         parse_tag!(parser, "map",
                    "tileset" => | attrs| {
-                        tilesets.push(try!(Tileset::new(parser, attrs, map_path.as_ref())));
+                        tilesets.push(Tileset::parse_map_tileset(parser, attrs, map_path, loader)?);
                         Ok(())
                    },
                    "layer" => |attrs| {
-                        layers.push(try!(Layer::new(parser, attrs, w)));
+                        layers.push(try!(Layer::new(parser, attrs, w, infinite, layer_index)));
+                        layer_index += 1;
                         Ok(())
                    },
                    "imagelayer" => |attrs| {
-                        image_layers.push(try!(ImageLayer::new(parser, attrs)));
+                        image_layers.push(try!(ImageLayer::new(parser, attrs, layer_index)));
+                        layer_index += 1;
                         Ok(())
                    },
                    "properties" => |_| {
@@ -270,22 +399,43 @@ impl Map {
                         Ok(())
                    },
                    "objectgroup" => |attrs| {
-                       object_groups.push(try!(ObjectGroup::new(parser, attrs)));
+                       let mut templates = |source: &str| loader.object_template(map_path, source, &tilesets);
+                       object_groups.push(ObjectGroup::new(parser, attrs, Some(layer_index), &mut templates)?);
+                       layer_index += 1;
+                       Ok(())
+                   },
+                   "group" => |attrs| {
+                       let mut templates = |source: &str| loader.object_template(map_path, source, &tilesets);
+                       groups.push(Group::new(parser, attrs, w, infinite, layer_index, &mut templates)?);
+                       layer_index += 1;
                        Ok(())
                    });
-        Ok(Map {version: v, orientation: o,
+        Ok(Map {version: v, tiled_version, orientation: o,
+                render_order: render_order.unwrap_or(RenderOrder::RightDown),
+                compression_level: compression_level.unwrap_or(-1),
                 width: w, height: h,
                 tile_width: tw, tile_height: th,
+                hex_side_length,
+                stagger_axis,
+                stagger_index,
+                parallax_origin_x: parallax_origin_x.unwrap_or(0.0),
+                parallax_origin_y: parallax_origin_y.unwrap_or(0.0),
                 tilesets,
                 layers,
                 image_layers,
                 object_groups,
+                groups,
                 properties,
-                background_colour: c,})
+                background_colour: c,
+                infinite,
+                next_layer_id,
+                next_object_id})
     }
 
-    /// This function will return the correct Tileset given a GID.
-    pub fn get_tileset_by_gid(&self, gid: u32) -> Option<&Tileset> {
+    /// This function will return the correct Tileset given a GID. Any flip
+    /// flags set on the GID are ignored.
+    pub fn get_tileset_by_gid(&self, gid: u32) -> Option<&MapTileset> {
+        let gid = gid & !ALL_FLIP_FLAGS;
         let mut maximum_gid: i32 = -1;
         let mut maximum_ts = None;
         for tileset in self.tilesets.iter() {
@@ -296,6 +446,23 @@ impl Map {
         }
         maximum_ts
     }
+
+    /// Returns the map's top level layers in the order they appear in the
+    /// file, which is also the order they should be drawn in.
+    pub fn ordered_layers(&self) -> Vec<LayerRef<'_>> {
+        ordered_layers(&self.layers, &self.image_layers, &self.object_groups, &self.groups)
+    }
+
+    /// Returns every tile layer, image layer and object group of the map,
+    /// including the ones nested in groups, in drawing order. The opacity,
+    /// visibility, offset and parallax factors of the parent groups are
+    /// combined into each returned layer.
+    pub fn flatten_layers(&self) -> Vec<FlattenedLayer<'_>> {
+        let mut flattened = Vec::new();
+        let root = GroupState::root();
+        flatten_into(&mut flattened, &root, &self.layers, &self.image_layers, &self.object_groups, &self.groups);
+        flattened
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
@@ -320,61 +487,450 @@ impl FromStr for Orientation {
     }
 }
 
-/// A tileset, usually the tilesheet image.
-#[derive(Debug, PartialEq, Clone)]
-pub struct Tileset {
+impl fmt::Display for Orientation {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let s = match *self {
+            Orientation::Orthogonal => "orthogonal",
+            Orientation::Isometric => "isometric",
+            Orientation::Staggered => "staggered",
+            Orientation::Hexagonal => "hexagonal",
+        };
+        fmt.write_str(s)
+    }
+}
+
+/// The order in which the tiles of a layer are drawn.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum RenderOrder {
+    RightDown,
+    RightUp,
+    LeftDown,
+    LeftUp,
+}
+
+impl FromStr for RenderOrder {
+    type Err = ParseTileError;
+
+    fn from_str(s: &str) -> Result<RenderOrder, ParseTileError> {
+        match s {
+            "right-down" => Ok(RenderOrder::RightDown),
+            "right-up" => Ok(RenderOrder::RightUp),
+            "left-down" => Ok(RenderOrder::LeftDown),
+            "left-up" => Ok(RenderOrder::LeftUp),
+            _ => Err(ParseTileError::RenderOrderError)
+        }
+    }
+}
+
+impl fmt::Display for RenderOrder {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let s = match *self {
+            RenderOrder::RightDown => "right-down",
+            RenderOrder::RightUp => "right-up",
+            RenderOrder::LeftDown => "left-down",
+            RenderOrder::LeftUp => "left-up",
+        };
+        fmt.write_str(s)
+    }
+}
+
+/// The axis along which every other row or column of a staggered or
+/// hexagonal map is shifted.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum StaggerAxis {
+    X,
+    Y,
+}
+
+impl FromStr for StaggerAxis {
+    type Err = ParseTileError;
+
+    fn from_str(s: &str) -> Result<StaggerAxis, ParseTileError> {
+        match s {
+            "x" => Ok(StaggerAxis::X),
+            "y" => Ok(StaggerAxis::Y),
+            _ => Err(ParseTileError::StaggerError)
+        }
+    }
+}
+
+impl fmt::Display for StaggerAxis {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(match *self {
+            StaggerAxis::X => "x",
+            StaggerAxis::Y => "y",
+        })
+    }
+}
+
+/// Whether the odd or the even rows or columns of a staggered or hexagonal
+/// map are shifted.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum StaggerIndex {
+    Odd,
+    Even,
+}
+
+impl FromStr for StaggerIndex {
+    type Err = ParseTileError;
+
+    fn from_str(s: &str) -> Result<StaggerIndex, ParseTileError> {
+        match s {
+            "odd" => Ok(StaggerIndex::Odd),
+            "even" => Ok(StaggerIndex::Even),
+            _ => Err(ParseTileError::StaggerError)
+        }
+    }
+}
+
+impl fmt::Display for StaggerIndex {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(match *self {
+            StaggerIndex::Odd => "odd",
+            StaggerIndex::Even => "even",
+        })
+    }
+}
+
+/// How the lines of a text object are aligned horizontally.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum HorizontalAlignment {
+    Left,
+    Center,
+    Right,
+    Justify,
+}
+
+impl FromStr for HorizontalAlignment {
+    type Err = ParseTileError;
+
+    fn from_str(s: &str) -> Result<HorizontalAlignment, ParseTileError> {
+        match s {
+            "left" => Ok(HorizontalAlignment::Left),
+            "center" => Ok(HorizontalAlignment::Center),
+            "right" => Ok(HorizontalAlignment::Right),
+            "justify" => Ok(HorizontalAlignment::Justify),
+            _ => Err(ParseTileError::AlignmentError)
+        }
+    }
+}
+
+impl fmt::Display for HorizontalAlignment {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let s = match *self {
+            HorizontalAlignment::Left => "left",
+            HorizontalAlignment::Center => "center",
+            HorizontalAlignment::Right => "right",
+            HorizontalAlignment::Justify => "justify",
+        };
+        fmt.write_str(s)
+    }
+}
+
+/// How the text of a text object is aligned vertically within the object.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum VerticalAlignment {
+    Top,
+    Center,
+    Bottom,
+}
+
+impl FromStr for VerticalAlignment {
+    type Err = ParseTileError;
+
+    fn from_str(s: &str) -> Result<VerticalAlignment, ParseTileError> {
+        match s {
+            "top" => Ok(VerticalAlignment::Top),
+            "center" => Ok(VerticalAlignment::Center),
+            "bottom" => Ok(VerticalAlignment::Bottom),
+            _ => Err(ParseTileError::AlignmentError)
+        }
+    }
+}
+
+impl fmt::Display for VerticalAlignment {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let s = match *self {
+            VerticalAlignment::Top => "top",
+            VerticalAlignment::Center => "center",
+            VerticalAlignment::Bottom => "bottom",
+        };
+        fmt.write_str(s)
+    }
+}
+
+/// Which point of a tile object its position refers to.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum ObjectAlignment {
+    /// Bottom left on orthogonal maps and bottom center on isometric ones.
+    Unspecified,
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl ObjectAlignment {
+    /// The anchor point as a fraction of the object's width and height,
+    /// measured from its top left corner.
+    pub fn origin(&self, orientation: Orientation) -> (f32, f32) {
+        match *self {
+            ObjectAlignment::Unspecified => match orientation {
+                Orientation::Isometric => (0.5, 1.0),
+                _ => (0.0, 1.0),
+            },
+            ObjectAlignment::TopLeft => (0.0, 0.0),
+            ObjectAlignment::Top => (0.5, 0.0),
+            ObjectAlignment::TopRight => (1.0, 0.0),
+            ObjectAlignment::Left => (0.0, 0.5),
+            ObjectAlignment::Center => (0.5, 0.5),
+            ObjectAlignment::Right => (1.0, 0.5),
+            ObjectAlignment::BottomLeft => (0.0, 1.0),
+            ObjectAlignment::Bottom => (0.5, 1.0),
+            ObjectAlignment::BottomRight => (1.0, 1.0),
+        }
+    }
+}
+
+impl FromStr for ObjectAlignment {
+    type Err = ParseTileError;
+
+    fn from_str(s: &str) -> Result<ObjectAlignment, ParseTileError> {
+        match s {
+            "unspecified" => Ok(ObjectAlignment::Unspecified),
+            "topleft" => Ok(ObjectAlignment::TopLeft),
+            "top" => Ok(ObjectAlignment::Top),
+            "topright" => Ok(ObjectAlignment::TopRight),
+            "left" => Ok(ObjectAlignment::Left),
+            "center" => Ok(ObjectAlignment::Center),
+            "right" => Ok(ObjectAlignment::Right),
+            "bottomleft" => Ok(ObjectAlignment::BottomLeft),
+            "bottom" => Ok(ObjectAlignment::Bottom),
+            "bottomright" => Ok(ObjectAlignment::BottomRight),
+            _ => Err(ParseTileError::AlignmentError)
+        }
+    }
+}
+
+impl fmt::Display for ObjectAlignment {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let s = match *self {
+            ObjectAlignment::Unspecified => "unspecified",
+            ObjectAlignment::TopLeft => "topleft",
+            ObjectAlignment::Top => "top",
+            ObjectAlignment::TopRight => "topright",
+            ObjectAlignment::Left => "left",
+            ObjectAlignment::Center => "center",
+            ObjectAlignment::Right => "right",
+            ObjectAlignment::BottomLeft => "bottomleft",
+            ObjectAlignment::Bottom => "bottom",
+            ObjectAlignment::BottomRight => "bottomright",
+        };
+        fmt.write_str(s)
+    }
+}
+
+/// The size tiles of a tileset are drawn at.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum TileRenderSize {
+    /// Tiles are drawn at the size of the tile.
+    Tile,
+    /// Tiles are drawn at the tile size of the map.
+    Grid,
+}
+
+impl FromStr for TileRenderSize {
+    type Err = ParseTileError;
+
+    fn from_str(s: &str) -> Result<TileRenderSize, ParseTileError> {
+        match s {
+            "tile" => Ok(TileRenderSize::Tile),
+            "grid" => Ok(TileRenderSize::Grid),
+            _ => Err(ParseTileError::TileRenderSizeError)
+        }
+    }
+}
+
+impl fmt::Display for TileRenderSize {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(match *self {
+            TileRenderSize::Tile => "tile",
+            TileRenderSize::Grid => "grid",
+        })
+    }
+}
+
+/// How tiles are fitted when they are drawn at a size different from their
+/// own, see `TileRenderSize`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum FillMode {
+    Stretch,
+    /// Keep the tile's aspect ratio, leaving space at the sides if needed.
+    PreserveAspectFit,
+}
+
+impl FromStr for FillMode {
+    type Err = ParseTileError;
+
+    fn from_str(s: &str) -> Result<FillMode, ParseTileError> {
+        match s {
+            "stretch" => Ok(FillMode::Stretch),
+            "preserve-aspect-fit" => Ok(FillMode::PreserveAspectFit),
+            _ => Err(ParseTileError::FillModeError)
+        }
+    }
+}
+
+impl fmt::Display for FillMode {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(match *self {
+            FillMode::Stretch => "stretch",
+            FillMode::PreserveAspectFit => "preserve-aspect-fit",
+        })
+    }
+}
+
+/// The grid used when placing the tiles of a tileset in isometric maps or
+/// when painting terrain.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Grid {
+    /// Either `Orthogonal` or `Isometric`.
+    pub orientation: Orientation,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Grid {
+    fn new<R: Read>(parser: &mut EventReader<R>, attrs: Vec<OwnedAttribute>) -> Result<Grid, TiledError> {
+        let (o, (w, h)) = get_attrs!(
+            attrs,
+            optionals: [("orientation", orientation, |v:String| v.parse().ok())],
+            required: [("width", width, |v:String| v.parse().ok()),
+                       ("height", height, |v:String| v.parse().ok())],
+            TiledError::MalformedAttributes("grid must have a width and height with correct types".to_string()));
+        parse_tag!(parser, "grid", "" => |_| Ok(()));
+        Ok(Grid {orientation: o.unwrap_or(Orientation::Orthogonal), width: w, height: h})
+    }
+}
+
+/// A tileset as used by a map. The tileset itself may be shared with other
+/// maps loaded by the same `Loader`, the GIDs it covers belong to the map.
+///
+/// Derefs to the `Tileset`.
+#[derive(Debug, Clone)]
+pub struct MapTileset {
     /// The GID of the first tile stored
     pub first_gid: u32,
+    pub tileset: Arc<Tileset>,
+    /// The path of the tileset file for external tilesets. Image sources of
+    /// the tileset are relative to this file.
+    pub source: Option<PathBuf>,
+}
+
+impl Deref for MapTileset {
+    type Target = Tileset;
+
+    fn deref(&self) -> &Tileset {
+        &self.tileset
+    }
+}
+
+// A tile count or number of columns left out of the file is the same as
+// giving the one worked out from the image.
+impl PartialEq for Tileset {
+    fn eq(&self, other: &Tileset) -> bool {
+        self.name == other.name
+            && self.tile_width == other.tile_width
+            && self.tile_height == other.tile_height
+            && self.spacing == other.spacing
+            && self.margin == other.margin
+            && self.tile_count() == other.tile_count()
+            && self.columns() == other.columns()
+            && self.object_alignment == other.object_alignment
+            && self.tile_offset_x == other.tile_offset_x
+            && self.tile_offset_y == other.tile_offset_y
+            && self.grid == other.grid
+            && self.tile_render_size == other.tile_render_size
+            && self.fill_mode == other.fill_mode
+            && self.properties == other.properties
+            && self.images == other.images
+            && self.tiles == other.tiles
+            && self.terrains == other.terrains
+            && self.wang_sets == other.wang_sets
+    }
+}
+
+// Where a tileset was loaded from doesn't matter, so a map with an external
+// tileset is equal to the same map with the tileset embedded.
+impl PartialEq for MapTileset {
+    fn eq(&self, other: &MapTileset) -> bool {
+        self.first_gid == other.first_gid && self.tileset == other.tileset
+    }
+}
+
+/// A tileset, usually the tilesheet image.
+#[derive(Debug, Clone)]
+pub struct Tileset {
     pub name: String,
     pub tile_width: u32,
     pub tile_height: u32,
     pub spacing: u32,
     pub margin: u32,
+    /// The number of tiles in the tileset, if the file gives it. See
+    /// `Tileset::tile_count` for the number of tiles actually used.
+    pub tile_count: Option<u32>,
+    /// The number of tile columns in the tileset image, if the file gives
+    /// it. See `Tileset::columns` for the number of columns actually used.
+    pub columns: Option<u32>,
+    /// Where tile objects using this tileset are anchored.
+    pub object_alignment: ObjectAlignment,
+    /// The offset in pixels applied when drawing the tiles of this tileset,
+    /// with positive `y` going down. Tiles taller than the map's tiles use it
+    /// to line up with the grid.
+    pub tile_offset_x: i32,
+    pub tile_offset_y: i32,
+    pub grid: Option<Grid>,
+    pub tile_render_size: TileRenderSize,
+    pub fill_mode: FillMode,
+    pub properties: Properties,
     /// The Tiled spec says that a tileset can have mutliple images so a `Vec`
     /// is used. Usually you will only use one.
     pub images: Vec<Image>,
-    pub tiles: Vec<Tile>
+    pub tiles: Vec<Tile>,
+    /// The terrain types of tilesets made before Tiled 1.5, which replaced
+    /// them with Wang sets. Tiles refer to them through `Tile::terrain`.
+    pub terrains: Vec<Terrain>,
+    pub wang_sets: Vec<WangSet>,
 }
 
 impl Tileset {
-    fn new<R: Read, P: AsRef<Path>>(parser: &mut EventReader<R>, attrs: Vec<OwnedAttribute>, map_path: Option<P>) -> Result<Tileset, TiledError> {
-        Tileset::new_internal(parser, &attrs)
-            .or_else(|_| { Tileset::new_reference(&attrs, map_path) })
+    // Parses a `<tileset>` element of a map, which either embeds the
+    // tileset or references a tileset file through `source`.
+    fn parse_map_tileset<R: Read, RR: ResourceReader>(parser: &mut EventReader<R>, attrs: Vec<OwnedAttribute>, map_path: Option<&Path>,
+                                                      loader: &mut Loader<RR>) -> Result<MapTileset, TiledError> {
+        if attrs.iter().any(|attr| attr.name.local_name == "source") {
+            Tileset::new_reference(&attrs, map_path, loader)
+        } else {
+            Tileset::new_internal(parser, &attrs)
+        }
     }
 
-    fn new_internal<R: Read>(parser: &mut EventReader<R>, attrs: &Vec<OwnedAttribute>) -> Result<Tileset, TiledError> {
-        let ((spacing, margin), (first_gid, name, width, height)) = get_attrs!(
+    fn new_internal<R: Read>(parser: &mut EventReader<R>, attrs: &Vec<OwnedAttribute>) -> Result<MapTileset, TiledError> {
+        let ((), first_gid) = get_attrs!(
            attrs,
-           optionals: [("spacing", spacing, |v:String| v.parse().ok()),
-                       ("margin", margin, |v:String| v.parse().ok())],
-           required: [("firstgid", first_gid, |v:String| v.parse().ok()),
-                      ("name", name, |v| Some(v)),
-                      ("tilewidth", width, |v:String| v.parse().ok()),
-                      ("tileheight", height, |v:String| v.parse().ok())],
+           optionals: [],
+           required: [("firstgid", first_gid, |v:String| v.parse().ok())],
            TiledError::MalformedAttributes("tileset must have a firstgid, name tile width and height with correct types".to_string()));
-
-        let mut images = Vec::new();
-        let mut tiles = Vec::new();
-        parse_tag!(parser, "tileset",
-                   "image" => |attrs| {
-                        images.push(try!(Image::new(parser, attrs)));
-                        Ok(())
-                   },
-                   "tile" => |attrs| {
-                        tiles.push(try!(Tile::new(parser, attrs)));
-                        Ok(())
-                   });
-
-        Ok(Tileset {first_gid: first_gid,
-                    name: name,
-                    tile_width: width, tile_height: height,
-                    spacing: spacing.unwrap_or(0),
-                    margin: margin.unwrap_or(0),
-                    images: images,
-                    tiles: tiles})
+        let tileset = Tileset::parse_tileset(parser, attrs)?;
+        Ok(MapTileset {first_gid, tileset: Arc::new(tileset), source: None})
     }
 
-    fn new_reference<P: AsRef<Path>>(attrs: &Vec<OwnedAttribute>, map_path: Option<P>) -> Result<Tileset, TiledError> {
+    fn new_reference<RR: ResourceReader>(attrs: &Vec<OwnedAttribute>, map_path: Option<&Path>, loader: &mut Loader<RR>) -> Result<MapTileset, TiledError> {
         let ((), (first_gid, source)) = get_attrs!(
            attrs,
            optionals: [],
@@ -382,18 +938,17 @@ impl Tileset {
                       ("source", name, |v| Some(v))],
            TiledError::MalformedAttributes("tileset must have a firstgid, name tile width and height with correct types".to_string()));
 
-        let tileset_path = map_path.ok_or(TiledError::Other("Maps with external tilesets must know their file location.  See parse_with_path(Path).".to_string()))?.as_ref().with_file_name(source);
-        let file = File::open(&tileset_path).map_err(|_| TiledError::Other(format!("External tileset file not found: {:?}", tileset_path)))?;
-        Tileset::new_external(file, first_gid)
+        let map_path = map_path.ok_or(TiledError::Other("Maps with external tilesets must know their file location.  See parse_with_path(Path).".to_string()))?;
+        loader.map_tileset(map_path, &source, first_gid)
     }
 
-    fn new_external<R: Read>(file: R, first_gid: u32) -> Result<Tileset, TiledError> {
+    fn new_external<R: Read>(file: R) -> Result<Tileset, TiledError> {
         let mut tileset_parser = EventReader::new(file);
         loop {
             match try!(tileset_parser.next().map_err(TiledError::XmlDecodingError)) {
                 XmlEvent::StartElement {name, attributes, ..}  => {
                     if name.local_name == "tileset" {
-                        return Tileset::parse_external_tileset(first_gid, &mut tileset_parser, &attributes)
+                        return Tileset::parse_tileset(&mut tileset_parser, &attributes)
                     }
                 }
                 XmlEvent::EndDocument => return Err(TiledError::PrematureEnd("Tileset Document ended before map was parsed".to_string())),
@@ -402,11 +957,113 @@ impl Tileset {
         }
     }
 
-    fn parse_external_tileset<R: Read>(first_gid: u32, parser: &mut EventReader<R>, attrs: &Vec<OwnedAttribute>) -> Result<Tileset, TiledError> {
-        let ((spacing, margin), (name, width, height)) = get_attrs!(
+    /// Returns the data stored for the tile with the given id, if there is
+    /// any. Tiles without properties, collision shapes or animations usually
+    /// have no entry in `tiles`.
+    pub fn get_tile(&self, id: u32) -> Option<&Tile> {
+        self.tiles.iter().find(|tile| tile.id == id)
+    }
+
+    /// The number of tiles in the tileset. Tile ids go from 0 up to this
+    /// number for tilesets with an image. Unless the file says otherwise,
+    /// this is the number of tiles fitting in the image, or the number of
+    /// tiles of an image collection.
+    pub fn tile_count(&self) -> u32 {
+        self.tile_count.unwrap_or_else(|| self.image_grid().1)
+    }
+
+    /// The number of tile columns in the tileset image. Unless the file says
+    /// otherwise, this is the number of tiles fitting across the image. 0 for
+    /// image collection tilesets.
+    pub fn columns(&self) -> u32 {
+        self.columns.unwrap_or_else(|| self.image_grid().0)
+    }
+
+    /// Whether this is a collection of images, where every tile has its own
+    /// image, rather than a tileset cut from a single image.
+    pub fn is_image_collection(&self) -> bool {
+        self.images.is_empty()
+    }
+
+    /// The size of the tile with the given id. In image collection tilesets
+    /// this is the size of the part of the tile's own image it uses.
+    pub fn tile_size(&self, id: u32) -> (u32, u32) {
+        let tile = match self.get_tile(id) {
+            Some(tile) if self.is_image_collection() => tile,
+            _ => return (self.tile_width, self.tile_height),
+        };
+        let image = tile.images.first();
+        let size = |tile_size: Option<u32>, image_size: Option<i32>, offset: u32, default: u32| {
+            tile_size.or_else(|| image_size.map(|s| (s.max(0) as u32).saturating_sub(offset))).unwrap_or(default)
+        };
+        (size(tile.width, image.and_then(|i| i.width), tile.x, self.tile_width),
+         size(tile.height, image.and_then(|i| i.height), tile.y, self.tile_height))
+    }
+
+    /// Returns the image holding the tile with the given id and the rectangle
+    /// the tile covers in it, or `None` if the tileset has no such tile.
+    ///
+    /// For tilesets with a single image, the rectangle is worked out from
+    /// the tile size, `margin`, `spacing` and `columns`, and ids must be
+    /// below `tile_count`. In image collection tilesets each tile has its
+    /// own image, of which the rectangle covers the part given by the tile's
+    /// `x`, `y`, `width` and `height`. Ids of image collections can have
+    /// gaps, and any id without a tile gives `None`.
+    pub fn tile_source(&self, id: u32) -> Option<TileSource<'_>> {
+        match self.images.first() {
+            Some(image) => {
+                let columns = self.columns();
+                if id >= self.tile_count() || columns == 0 {
+                    return None;
+                }
+                let (column, row) = (id % columns, id / columns);
+                Some(TileSource {
+                    image,
+                    x: self.margin + column * (self.tile_width + self.spacing),
+                    y: self.margin + row * (self.tile_height + self.spacing),
+                    width: self.tile_width,
+                    height: self.tile_height,
+                })
+            }
+            None => {
+                let tile = self.get_tile(id)?;
+                let image = tile.images.first()?;
+                let (width, height) = self.tile_size(id);
+                Some(TileSource {image, x: tile.x, y: tile.y, width, height})
+            }
+        }
+    }
+
+    /// Returns the collision shapes of the tile with the given id as the
+    /// tile appears when drawn with the given flips, in pixels relative to
+    /// the top left corner of the tile. Like when drawing, the diagonal flip
+    /// is applied first.
+    ///
+    /// The points of polygons and polylines come out with the object's
+    /// rotation already applied to them. Rectangles and ellipses which end
+    /// up turned by a multiple of 90 degrees are given as an unrotated box.
+    pub fn collision_shapes(&self, id: u32, flip_h: bool, flip_v: bool, flip_d: bool) -> Vec<CollisionShape<'_>> {
+        let group = match self.get_tile(id).and_then(|tile| tile.objectgroup.as_ref()) {
+            Some(group) => group,
+            None => return Vec::new(),
+        };
+        let (width, height) = self.tile_size(id);
+        let flip = TileFlip {width: width as f32, height: height as f32, flip_h, flip_v, flip_d};
+        group.objects.iter().map(|object| flip.apply(object)).collect()
+    }
+
+    // Parses the contents of a `<tileset>` element, which are the same for
+    // embedded tilesets and tileset files.
+    fn parse_tileset<R: Read>(parser: &mut EventReader<R>, attrs: &[OwnedAttribute]) -> Result<Tileset, TiledError> {
+        let ((spacing, margin, tile_count, columns, alignment, render_size, fill_mode), (name, width, height)) = get_attrs!(
             attrs,
             optionals: [("spacing", spacing, |v:String| v.parse().ok()),
-                        ("margin", margin, |v:String| v.parse().ok())],
+                        ("margin", margin, |v:String| v.parse().ok()),
+                        ("tilecount", tile_count, |v:String| v.parse().ok()),
+                        ("columns", columns, |v:String| v.parse().ok()),
+                        ("objectalignment", alignment, |v:String| v.parse().ok()),
+                        ("tilerendersize", render_size, |v:String| v.parse().ok()),
+                        ("fillmode", fill_mode, |v:String| v.parse().ok())],
             required: [("name", name, |v| Some(v)),
                        ("tilewidth", width, |v:String| v.parse().ok()),
                        ("tileheight", height, |v:String| v.parse().ok())],
@@ -414,7 +1071,30 @@ impl Tileset {
 
         let mut images = Vec::new();
         let mut tiles = Vec::new();
+        let mut tile_offset = (0, 0);
+        let mut grid = None;
+        let mut properties = HashMap::new();
+        let mut terrains = Vec::new();
+        let mut wang_sets = Vec::new();
         parse_tag!(parser, "tileset",
+                   "tileoffset" => |attrs: Vec<OwnedAttribute>| {
+                       let (offset, ()) = get_attrs!(
+                           attrs,
+                           optionals: [("x", x, |v:String| v.parse().ok()),
+                                       ("y", y, |v:String| v.parse().ok())],
+                           required: [],
+                           TiledError::MalformedAttributes("tileoffset must have an x and y with correct types".to_string()));
+                       tile_offset = (offset.0.unwrap_or(0), offset.1.unwrap_or(0));
+                       Ok(())
+                   },
+                   "grid" => |attrs| {
+                       grid = Some(Grid::new(parser, attrs)?);
+                       Ok(())
+                   },
+                   "properties" => |_| {
+                       properties = parse_properties(parser)?;
+                       Ok(())
+                   },
                    "image" => |attrs| {
                        images.push(try!(Image::new(parser, attrs)));
                        Ok(())
@@ -422,41 +1102,227 @@ impl Tileset {
                    "tile" => |attrs| {
                        tiles.push(try!(Tile::new(parser, attrs)));
                        Ok(())
+                   },
+                   "terraintypes" => |_| {
+                       terrains = parse_terrain_types(parser)?;
+                       Ok(())
+                   },
+                   "wangsets" => |_| {
+                       wang_sets = parse_wang_sets(parser)?;
+                       Ok(())
                    });
 
-        Ok(Tileset {first_gid: first_gid,
-                    name: name,
+        Ok(Tileset {name,
                     tile_width: width, tile_height: height,
                     spacing: spacing.unwrap_or(0),
                     margin: margin.unwrap_or(0),
-                    images: images,
-                    tiles: tiles})
+                    tile_count,
+                    columns,
+                    object_alignment: alignment.unwrap_or(ObjectAlignment::Unspecified),
+                    tile_offset_x: tile_offset.0,
+                    tile_offset_y: tile_offset.1,
+                    grid,
+                    tile_render_size: render_size.unwrap_or(TileRenderSize::Tile),
+                    fill_mode: fill_mode.unwrap_or(FillMode::Stretch),
+                    properties,
+                    images,
+                    tiles,
+                    terrains,
+                    wang_sets})
     }
+
+    // The number of columns and tiles that fit in the tileset image, used
+    // when the file doesn't say. Image collection tilesets have no columns
+    // and one tile per image.
+    fn image_grid(&self) -> (u32, u32) {
+        match self.images.first() {
+            Some(image) => {
+                let count = |size: Option<i32>, tile_size: u32| {
+                    let usable = (size.unwrap_or(0).max(0) as u32).saturating_sub(self.margin.saturating_mul(2))
+                        .saturating_add(self.spacing);
+                    usable / tile_size.saturating_add(self.spacing).max(1)
+                };
+                let columns = count(image.width, self.tile_width);
+                (columns, columns.saturating_mul(count(image.height, self.tile_height)))
+            }
+            None => (0, self.tiles.len() as u32),
+        }
+    }
+}
+
+/// Where a tile's pixels are found, as returned by `Tileset::tile_source`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct TileSource<'a> {
+    pub image: &'a Image,
+    /// The top left corner of the tile in the image, in pixels.
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
 }
 
+/// A collision shape of a tile, as returned by `Tileset::collision_shapes`.
 #[derive(Debug, PartialEq, Clone)]
-pub struct Tile {
-    pub id: u32,
-    pub images: Vec<Image>,
-    pub properties: Properties,
-    pub objectgroup: Option<ObjectGroup>,
-    pub animation: Option<Vec<Frame>>,
+pub struct CollisionShape<'a> {
+    /// The object the shape was made from, for its name, type and
+    /// properties. Its position and shape are as stored in the tileset.
+    pub object: &'a Object,
+    /// The position of the shape relative to the top left corner of the
+    /// tile, which the shape rotates around like an object.
+    pub x: f32,
+    pub y: f32,
+    /// The clockwise rotation in degrees.
+    pub rotation: f32,
+    pub shape: ObjectShape,
 }
 
-impl Tile {
-    fn new<R: Read>(parser: &mut EventReader<R>, attrs: Vec<OwnedAttribute>) -> Result<Tile, TiledError> {
-        let (_, i) = get_attrs!(
-            attrs,
-            optionals: [],
-            required: [("id", id, |v:String| v.parse().ok())],
-            TiledError::MalformedAttributes("tile must have an id with the correct type".to_string()));
+// Maps points in a tile to where they end up when the tile is drawn flipped.
+struct TileFlip {
+    width: f32,
+    height: f32,
+    flip_h: bool,
+    flip_v: bool,
+    flip_d: bool,
+}
 
-        let mut images = Vec::new();
-        let mut properties = HashMap::new();
-        let mut objectgroup = None;
-        let mut animation = None;
-        parse_tag!(parser, "tile",
-                   "image" => |attrs| {
+impl TileFlip {
+    fn point(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let (mut x, mut y, mut width, mut height) = (x, y, self.width, self.height);
+        if self.flip_d {
+            std::mem::swap(&mut x, &mut y);
+            std::mem::swap(&mut width, &mut height);
+        }
+        (if self.flip_h { width - x } else { x }, if self.flip_v { height - y } else { y })
+    }
+
+    fn vector(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let (x, y) = if self.flip_d { (y, x) } else { (x, y) };
+        (if self.flip_h { -x } else { x }, if self.flip_v { -y } else { y })
+    }
+
+    // Splits the flip into a turn, in degrees, and whether it mirrors. A
+    // mirroring flip turns the geometry after mirroring it horizontally.
+    fn turn(&self) -> (f32, bool) {
+        let (ax, ay) = self.vector((1.0, 0.0));
+        let (bx, by) = self.vector((0.0, 1.0));
+        let mirrors = ax * by - ay * bx < 0.0;
+        let (x, y) = if mirrors { (-ax, -ay) } else { (ax, ay) };
+        (y.atan2(x).to_degrees(), mirrors)
+    }
+
+    fn apply<'a>(&self, object: &'a Object) -> CollisionShape<'a> {
+        let (turn, mirrors) = self.turn();
+        let rotation = if mirrors { turn - object.rotation } else { turn + object.rotation };
+        let origin = self.point((object.x, object.y));
+        let mirror = |(x, y): (f32, f32)| if mirrors { (-x, y) } else { (x, y) };
+        let shape = |x: f32, y: f32, rotation: f32, shape: ObjectShape| {
+            CollisionShape {object, x, y, rotation: rotation.rem_euclid(360.0), shape}
+        };
+        // Boxes grow right and down from their position, so a mirrored box
+        // is moved to start from its other side.
+        let boxed = |width: f32| {
+            let (dx, dy) = if mirrors { rotate(rotation, (-width, 0.0)) } else { (0.0, 0.0) };
+            (origin.0 + dx, origin.1 + dy)
+        };
+        match object.shape {
+            ObjectShape::Rect {width, height} | ObjectShape::Ellipse {width, height} => {
+                let (x, y) = boxed(width);
+                let quarters = rotation / 90.0;
+                if (quarters - quarters.round()).abs() > 1e-4 {
+                    return shape(x, y, rotation, object.shape.clone());
+                }
+                let corners = [(0.0, 0.0), (width, 0.0), (0.0, height), (width, height)];
+                let corners: Vec<(f32, f32)> = corners.iter().map(|&c| rotate(rotation, c)).collect();
+                let left = corners.iter().map(|c| c.0).fold(f32::INFINITY, f32::min);
+                let top = corners.iter().map(|c| c.1).fold(f32::INFINITY, f32::min);
+                let (width, height) = if quarters.round() as i32 % 2 == 0 { (width, height) } else { (height, width) };
+                let unrotated = match object.shape {
+                    ObjectShape::Rect {..} => ObjectShape::Rect {width, height},
+                    _ => ObjectShape::Ellipse {width, height},
+                };
+                shape(x + left, y + top, 0.0, unrotated)
+            }
+            ObjectShape::Text {width, ..} => {
+                let (x, y) = boxed(width);
+                shape(x, y, rotation, object.shape.clone())
+            }
+            ObjectShape::Polyline {ref points} => {
+                let points = points.iter().map(|&p| rotate(rotation, mirror(p))).collect();
+                shape(origin.0, origin.1, 0.0, ObjectShape::Polyline {points})
+            }
+            ObjectShape::Polygon {ref points} => {
+                let points = points.iter().map(|&p| rotate(rotation, mirror(p))).collect();
+                shape(origin.0, origin.1, 0.0, ObjectShape::Polygon {points})
+            }
+            ObjectShape::Point => shape(origin.0, origin.1, 0.0, ObjectShape::Point),
+        }
+    }
+}
+
+// Turns a vector clockwise, exactly when by a multiple of 90 degrees.
+fn rotate(degrees: f32, (x, y): (f32, f32)) -> (f32, f32) {
+    let quarters = degrees / 90.0;
+    if (quarters - quarters.round()).abs() < 1e-4 {
+        return match (quarters.round() as i32).rem_euclid(4) {
+            0 => (x, y),
+            1 => (-y, x),
+            2 => (-x, -y),
+            _ => (y, -x),
+        };
+    }
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    (x * cos - y * sin, x * sin + y * cos)
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Tile {
+    pub id: u32,
+    /// The tile's class, called its type before Tiled 1.9. Empty if it has
+    /// none.
+    pub tile_type: String,
+    /// The relative chance of the tile being picked when Tiled, or an
+    /// `Autotiler`, chooses between tiles. 1.0 unless set.
+    pub probability: f32,
+    /// The tile's own image, in image collection tilesets.
+    pub images: Vec<Image>,
+    /// The part of the tile's image used for the tile, in image collection
+    /// tilesets. The rectangle starts at `x`, `y` and covers the rest of the
+    /// image when `width` and `height` aren't given.
+    pub x: u32,
+    pub y: u32,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// The indices into `Tileset::terrains` of the terrain at the top left,
+    /// top right, bottom left and bottom right corners of the tile, for
+    /// tilesets made before Tiled 1.5.
+    pub terrain: Option<[Option<u32>; 4]>,
+    pub properties: Properties,
+    pub objectgroup: Option<ObjectGroup>,
+    /// The frames the tile cycles through, if it is animated.
+    pub animation: Option<Vec<Frame>>,
+}
+
+impl Tile {
+    fn new<R: Read>(parser: &mut EventReader<R>, attrs: Vec<OwnedAttribute>) -> Result<Tile, TiledError> {
+        let ((tt, c, p, x, y, w, h, t), i) = get_attrs!(
+            attrs,
+            optionals: [("type", tile_type, |v:String| v.parse().ok()),
+                        ("class", class, |v:String| v.parse().ok()),
+                        ("probability", probability, |v:String| v.parse().ok()),
+                        ("x", x, |v:String| v.parse().ok()),
+                        ("y", y, |v:String| v.parse().ok()),
+                        ("width", width, |v:String| v.parse().ok()),
+                        ("height", height, |v:String| v.parse().ok()),
+                        ("terrain", terrain, |v:String| parse_terrain_corners(&v))],
+            required: [("id", id, |v:String| v.parse().ok())],
+            TiledError::MalformedAttributes("tile must have an id with the correct type".to_string()));
+
+        let mut images = Vec::new();
+        let mut properties = HashMap::new();
+        let mut objectgroup = None;
+        let mut animation = None;
+        parse_tag!(parser, "tile",
+                   "image" => |attrs| {
                        images.push(Image::new(parser, attrs)?);
                        Ok(())
                    },
@@ -465,98 +1331,596 @@ impl Tile {
                        Ok(())
                    },
                    "objectgroup" => |attrs| {
-                       objectgroup = Some(ObjectGroup::new(parser, attrs)?);
+                       objectgroup = Some(ObjectGroup::new(parser, attrs, None, &mut no_templates)?);
                        Ok(())
                    },
                    "animation" => |_| {
                        animation = Some(parse_animation(parser)?);
                        Ok(())
                    });
-        Ok(Tile {id: i, images: images, properties: properties, objectgroup: objectgroup, animation: animation})
+        Ok(Tile {id: i, tile_type: c.or(tt).unwrap_or_default(), probability: p.unwrap_or(1.0),
+                 images, x: x.unwrap_or(0), y: y.unwrap_or(0), width: w, height: h, terrain: t,
+                 properties, objectgroup, animation})
+    }
+
+    /// The id of the tile shown `elapsed` milliseconds after the tile's
+    /// animation started, looping when the animation ends. Returns `None` if
+    /// the tile isn't animated. Animations whose frames all last 0
+    /// milliseconds stay on their first frame.
+    pub fn animation_frame(&self, elapsed: u64) -> Option<u32> {
+        let frames = self.animation.as_ref()?;
+        let first = frames.first()?;
+        let total: u64 = frames.iter().map(|frame| u64::from(frame.duration)).sum();
+        if total == 0 {
+            return Some(first.tile_id);
+        }
+        let mut time = elapsed % total;
+        for frame in frames {
+            if time < u64::from(frame.duration) {
+                return Some(frame.tile_id);
+            }
+            time -= u64::from(frame.duration);
+        }
+        Some(first.tile_id)
+    }
+}
+
+// Terrain corners are written as four comma separated indices, where an
+// empty index means the corner has no terrain.
+fn parse_terrain_corners(value: &str) -> Option<[Option<u32>; 4]> {
+    let mut corners = [None; 4];
+    let mut parts = value.split(',');
+    for corner in &mut corners {
+        let part = parts.next()?.trim();
+        if !part.is_empty() {
+            *corner = Some(part.parse().ok()?);
+        }
+    }
+    match parts.next() {
+        Some(_) => None,
+        None => Some(corners),
+    }
+}
+
+/// A terrain type of a tileset made before Tiled 1.5.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Terrain {
+    pub name: String,
+    /// The tile representing the terrain in Tiled's interface.
+    pub tile: Option<u32>,
+    pub properties: Properties,
+}
+
+impl Terrain {
+    fn new<R: Read>(parser: &mut EventReader<R>, attrs: Vec<OwnedAttribute>) -> Result<Terrain, TiledError> {
+        let (t, n) = get_attrs!(
+            attrs,
+            optionals: [("tile", tile, |v:String| v.parse::<i64>().ok())],
+            required: [("name", name, Some)],
+            TiledError::MalformedAttributes("terrain must have a name".to_string()));
+        let mut properties = HashMap::new();
+        parse_tag!(parser, "terrain",
+                   "properties" => |_| {
+                       properties = parse_properties(parser)?;
+                       Ok(())
+                   });
+        Ok(Terrain {name: n, tile: optional_tile(t), properties})
+    }
+}
+
+// Tiled writes -1 for a missing tile.
+fn optional_tile(tile: Option<i64>) -> Option<u32> {
+    match tile {
+        Some(tile) if tile >= 0 => Some(tile as u32),
+        _ => None,
+    }
+}
+
+/// Which parts of the tiles a Wang set matches on.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum WangSetType {
+    Corner,
+    Edge,
+    Mixed,
+}
+
+impl FromStr for WangSetType {
+    type Err = ParseTileError;
+
+    fn from_str(s: &str) -> Result<WangSetType, ParseTileError> {
+        match s {
+            "corner" => Ok(WangSetType::Corner),
+            "edge" => Ok(WangSetType::Edge),
+            "mixed" => Ok(WangSetType::Mixed),
+            _ => Err(ParseTileError::WangSetTypeError)
+        }
+    }
+}
+
+impl fmt::Display for WangSetType {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(match *self {
+            WangSetType::Corner => "corner",
+            WangSetType::Edge => "edge",
+            WangSetType::Mixed => "mixed",
+        })
+    }
+}
+
+/// The colours of the edges and corners of a tile in a Wang set, starting at
+/// the top edge and going clockwise: top, top right, right, bottom right,
+/// bottom, bottom left, left and top left. Each value is an index into
+/// `WangSet::colours` plus one, 0 meaning no colour.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default, Hash)]
+pub struct WangId(pub [u8; 8]);
+
+impl WangId {
+    pub const TOP: usize = 0;
+    pub const TOP_RIGHT: usize = 1;
+    pub const RIGHT: usize = 2;
+    pub const BOTTOM_RIGHT: usize = 3;
+    pub const BOTTOM: usize = 4;
+    pub const BOTTOM_LEFT: usize = 5;
+    pub const LEFT: usize = 6;
+    pub const TOP_LEFT: usize = 7;
+}
+
+impl FromStr for WangId {
+    type Err = ParseTileError;
+
+    fn from_str(s: &str) -> Result<WangId, ParseTileError> {
+        let mut id = [0; 8];
+        let mut parts = s.split(',');
+        for colour in &mut id {
+            *colour = parts.next().and_then(|p| p.trim().parse().ok()).ok_or(ParseTileError::WangIdError)?;
+        }
+        match parts.next() {
+            Some(_) => Err(ParseTileError::WangIdError),
+            None => Ok(WangId(id)),
+        }
+    }
+}
+
+impl fmt::Display for WangId {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let parts: Vec<String> = self.0.iter().map(|c| c.to_string()).collect();
+        fmt.write_str(&parts.join(","))
+    }
+}
+
+/// A colour of a Wang set, which stands for a kind of terrain.
+#[derive(Debug, PartialEq, Clone)]
+pub struct WangColour {
+    pub name: String,
+    pub colour: Colour,
+    /// The tile representing the colour in Tiled's interface.
+    pub tile: Option<u32>,
+    /// How likely tiles with this colour are picked relative to other tiles.
+    pub probability: f32,
+    pub properties: Properties,
+}
+
+impl WangColour {
+    fn new<R: Read>(parser: &mut EventReader<R>, attrs: Vec<OwnedAttribute>) -> Result<WangColour, TiledError> {
+        let ((t, p), (n, c)) = get_attrs!(
+            attrs,
+            optionals: [("tile", tile, |v:String| v.parse::<i64>().ok()),
+                        ("probability", probability, |v:String| v.parse().ok())],
+            required: [("name", name, Some),
+                       ("color", colour, |v:String| v.parse().ok())],
+            TiledError::MalformedAttributes("wang colour must have a name and a colour".to_string()));
+        let mut properties = HashMap::new();
+        parse_tag!(parser, "wangcolor",
+                   "properties" => |_| {
+                       properties = parse_properties(parser)?;
+                       Ok(())
+                   });
+        Ok(WangColour {name: n, colour: c, tile: optional_tile(t), probability: p.unwrap_or(1.0), properties})
+    }
+}
+
+/// The Wang colours of one tile of a Wang set.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct WangTile {
+    pub tile_id: u32,
+    pub wang_id: WangId,
+}
+
+/// A set of tiles which Tiled can pick from automatically, based on the
+/// colours of their edges and corners.
+#[derive(Debug, PartialEq, Clone)]
+pub struct WangSet {
+    pub name: String,
+    pub set_type: WangSetType,
+    /// The tile representing the set in Tiled's interface.
+    pub tile: Option<u32>,
+    pub colours: Vec<WangColour>,
+    pub wang_tiles: Vec<WangTile>,
+    pub properties: Properties,
+}
+
+impl WangSet {
+    fn new<R: Read>(parser: &mut EventReader<R>, attrs: Vec<OwnedAttribute>) -> Result<WangSet, TiledError> {
+        let ((st, t), n) = get_attrs!(
+            attrs,
+            optionals: [("type", set_type, |v:String| v.parse().ok()),
+                        ("tile", tile, |v:String| v.parse::<i64>().ok())],
+            required: [("name", name, Some)],
+            TiledError::MalformedAttributes("wang set must have a name".to_string()));
+        let mut colours = Vec::new();
+        let mut wang_tiles = Vec::new();
+        let mut properties = HashMap::new();
+        parse_tag!(parser, "wangset",
+                   "wangcolor" => |attrs| {
+                       colours.push(WangColour::new(parser, attrs)?);
+                       Ok(())
+                   },
+                   "wangtile" => |attrs: Vec<OwnedAttribute>| {
+                       let ((), (tile_id, wang_id)) = get_attrs!(
+                           attrs,
+                           optionals: [],
+                           required: [("tileid", tile_id, |v:String| v.parse().ok()),
+                                      ("wangid", wang_id, |v:String| v.parse().ok())],
+                           TiledError::MalformedAttributes("wang tile must have a tileid and a wangid".to_string()));
+                       wang_tiles.push(WangTile {tile_id, wang_id});
+                       Ok(())
+                   },
+                   "properties" => |_| {
+                       properties = parse_properties(parser)?;
+                       Ok(())
+                   });
+        Ok(WangSet {
+            name: n,
+            // Wang sets from before Tiled 1.5 have no type and matched on
+            // both corners and edges.
+            set_type: st.unwrap_or(WangSetType::Mixed),
+            tile: optional_tile(t),
+            colours,
+            wang_tiles,
+            properties,
+        })
     }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Image {
-    /// The filepath of the image
+    /// The filepath of the image. Empty for images embedded in the file.
     pub source: String,
-    pub width: i32,
-    pub height: i32,
+    /// The size of the image in pixels. Tiled leaves it out for some image
+    /// layers.
+    pub width: Option<i32>,
+    pub height: Option<i32>,
     pub transparent_colour: Option<Colour>,
+    /// The file format of an embedded image, like `png`.
+    pub format: Option<String>,
+    /// The contents of an embedded image file, already base64 decoded.
+    pub data: Option<Vec<u8>>,
 }
 
 impl Image {
     fn new<R: Read>(parser: &mut EventReader<R>, attrs: Vec<OwnedAttribute>) -> Result<Image, TiledError> {
-        let (c, (s, w, h)) = get_attrs!(
+        let ((s, w, h, c, f), ()) = get_attrs!(
             attrs,
-            optionals: [("trans", trans, |v:String| v.parse().ok())],
-            required: [("source", source, |v| Some(v)),
-                       ("width", width, |v:String| v.parse().ok()),
-                       ("height", height, |v:String| v.parse().ok())],
-            TiledError::MalformedAttributes("image must have a source, width and height with correct types".to_string()));
+            optionals: [("source", source, Some),
+                        ("width", width, |v:String| v.parse().ok()),
+                        ("height", height, |v:String| v.parse().ok()),
+                        ("trans", trans, |v:String| v.parse().ok()),
+                        ("format", format, Some)],
+            required: [],
+            TiledError::MalformedAttributes("image must have a width and height with correct types".to_string()));
+
+        let mut data = None;
+        parse_tag!(parser, "image",
+                   "data" => |attrs: Vec<OwnedAttribute>| {
+                       match attrs.iter().find(|a| a.name.local_name == "encoding").map(|a| a.value.as_str()) {
+                           Some("base64") => {}
+                           e => return Err(TiledError::Other(format!("Unsupported image data encoding {:?}", e))),
+                       }
+                       data = Some(parse_base64(parser, "data")?);
+                       Ok(())
+                   });
+        if s.is_none() && data.is_none() {
+            return Err(TiledError::MalformedAttributes("image must have a source or embedded data".to_string()));
+        }
+        Ok(Image {source: s.unwrap_or_default(), width: w, height: h, transparent_colour: c, format: f, data})
+    }
+
+    /// Opens the image file through `resources`. `base` is the path of the
+    /// file the image was declared in: the map, or `MapTileset::source` for
+    /// images of external tilesets. Embedded images have no file, use `data`
+    /// for them instead.
+    pub fn read<RR: ResourceReader>(&self, base: &Path, resources: &mut RR) -> Result<RR::Resource, TiledError> {
+        if self.source.is_empty() {
+            return Err(TiledError::Other("Embedded images have no file to read".to_string()));
+        }
+        let path = resource::resolve_path(base, &self.source);
+        resources.read_from(&path).map_err(|_| TiledError::Other(format!("Image file not found: {:?}", path)))
+    }
+}
+
+const FLIPPED_HORIZONTALLY_FLAG: u32 = 0x80000000;
+const FLIPPED_VERTICALLY_FLAG: u32 = 0x40000000;
+const FLIPPED_DIAGONALLY_FLAG: u32 = 0x20000000;
+const ROTATED_HEXAGONAL_120_FLAG: u32 = 0x10000000;
+const ALL_FLIP_FLAGS: u32 = FLIPPED_HORIZONTALLY_FLAG | FLIPPED_VERTICALLY_FLAG |
+                            FLIPPED_DIAGONALLY_FLAG | ROTATED_HEXAGONAL_120_FLAG;
+
+/// A reference to a tile as stored in a layer or a tile object. Tiled keeps
+/// the flip flags in the highest bits of the GID; they are split out here.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct LayerTile {
+    /// The GID of the tile without any flip flags. 0 means there is no tile.
+    pub gid: u32,
+    pub flip_h: bool,
+    pub flip_v: bool,
+    /// Set when the tile is flipped along its top-left to bottom-right
+    /// diagonal, which together with the other flags is how Tiled stores
+    /// rotations.
+    pub flip_d: bool,
+    /// Only used by hexagonal maps, where it rotates the tile by 120 degrees.
+    pub rotate_hex_120: bool,
+}
+
+impl LayerTile {
+    /// Decodes a GID as stored in the file, including its flip flags.
+    pub fn new(raw_gid: u32) -> LayerTile {
+        LayerTile {
+            gid: raw_gid & !ALL_FLIP_FLAGS,
+            flip_h: raw_gid & FLIPPED_HORIZONTALLY_FLAG != 0,
+            flip_v: raw_gid & FLIPPED_VERTICALLY_FLAG != 0,
+            flip_d: raw_gid & FLIPPED_DIAGONALLY_FLAG != 0,
+            rotate_hex_120: raw_gid & ROTATED_HEXAGONAL_120_FLAG != 0,
+        }
+    }
+
+    /// Returns the GID as it would be stored in the file, with the flip flags.
+    pub fn raw_gid(&self) -> u32 {
+        let mut raw = self.gid;
+        if self.flip_h { raw |= FLIPPED_HORIZONTALLY_FLAG; }
+        if self.flip_v { raw |= FLIPPED_VERTICALLY_FLAG; }
+        if self.flip_d { raw |= FLIPPED_DIAGONALLY_FLAG; }
+        if self.rotate_hex_120 { raw |= ROTATED_HEXAGONAL_120_FLAG; }
+        raw
+    }
+
+    /// Whether this is an empty cell rather than an actual tile.
+    pub fn is_empty(&self) -> bool {
+        self.gid == 0
+    }
+
+    /// Returns the tileset the tile belongs to, or `None` for empty cells and
+    /// GIDs not covered by any of the map's tilesets.
+    pub fn tileset<'a>(&self, map: &'a Map) -> Option<&'a MapTileset> {
+        if self.is_empty() {
+            return None;
+        }
+        map.get_tileset_by_gid(self.gid)
+    }
+
+    /// Returns the id of the tile within its tileset, as used by `Tile::id`.
+    pub fn id(&self, map: &Map) -> Option<u32> {
+        self.tileset(map).map(|tileset| self.gid - tileset.first_gid)
+    }
+
+    /// Returns the data the tileset stores for the tile, see
+    /// `Tileset::get_tile`.
+    pub fn tile_data<'a>(&self, map: &'a Map) -> Option<&'a Tile> {
+        let tileset = self.tileset(map)?;
+        tileset.get_tile(self.gid - tileset.first_gid)
+    }
 
-        parse_tag!(parser, "image", "" => |_| Ok(()));
-        Ok(Image {source: s, width: w, height: h, transparent_colour: c})
+    /// Returns the collision shapes of the tile with its flips applied, see
+    /// `Tileset::collision_shapes`.
+    pub fn collision_shapes<'a>(&self, map: &'a Map) -> Vec<CollisionShape<'a>> {
+        match self.tileset(map) {
+            Some(tileset) => tileset.collision_shapes(self.gid - tileset.first_gid, self.flip_h, self.flip_v, self.flip_d),
+            None => Vec::new(),
+        }
     }
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Layer {
+    /// The id Tiled gave the layer, unique within the map. 0 for maps saved
+    /// by versions of Tiled without layer ids.
+    pub id: u32,
     pub name: String,
     pub opacity: f32,
     pub visible: bool,
-    /// The tiles are arranged in rows. Each tile is a number which can be used
-    ///  to find which tileset it belongs to and can then be rendered.
-    pub tiles: Vec<Vec<u32>>,
-    pub properties: Properties
+    /// Whether the layer is locked for editing in Tiled.
+    pub locked: bool,
+    /// A colour multiplied with the layer's tiles, objects or image.
+    pub tint_colour: Option<Colour>,
+    /// The layer's offset in pixels.
+    pub offset_x: f32,
+    pub offset_y: f32,
+    /// How fast the layer moves relative to the camera, where 1.0 is the
+    /// speed of the map and 0.0 doesn't move at all.
+    pub parallax_x: f32,
+    pub parallax_y: f32,
+    /// The tiles are arranged in rows. Each tile has a gid which can be used
+    /// to find which tileset it belongs to and can then be rendered.
+    pub tiles: Vec<Vec<LayerTile>>,
+    /// The tile data of an infinite map, split into chunks. Empty for finite
+    /// maps.
+    pub chunks: Vec<Chunk>,
+    pub properties: Properties,
+    /// The position of the layer amongst all the layers of its parent, be it
+    /// the map or a group.
+    pub layer_index: u32,
 }
 
 impl Layer {
-    fn new<R: Read>(parser: &mut EventReader<R>, attrs: Vec<OwnedAttribute>, width: u32) -> Result<Layer, TiledError> {
+    fn new<R: Read>(parser: &mut EventReader<R>, attrs: Vec<OwnedAttribute>, width: u32, infinite: bool, layer_index: u32) -> Result<Layer, TiledError> {
         let ((o, v), n) = get_attrs!(
             attrs,
             optionals: [("opacity", opacity, |v:String| v.parse().ok()),
                         ("visible", visible, |v:String| v.parse().ok().map(|x:i32| x == 1))],
             required: [("name", name, |v| Some(v))],
             TiledError::MalformedAttributes("layer must have a name".to_string()));
+        let common = LayerAttributes::new(&attrs)?;
         let mut tiles = Vec::new();
+        let mut chunks = Vec::new();
         let mut properties = HashMap::new();
         parse_tag!(parser, "layer",
                    "data" => |attrs| {
-                        tiles = try!(parse_data(parser, attrs, width));
+                        if infinite {
+                            chunks = parse_infinite_data(parser, attrs)?;
+                        } else {
+                            tiles = parse_data(parser, attrs, width)?;
+                        }
                         Ok(())
                    },
                    "properties" => |_| {
                         properties = try!(parse_properties(parser));
                         Ok(())
                    });
-        Ok(Layer {name: n, opacity: o.unwrap_or(1.0), visible: v.unwrap_or(true), tiles: tiles,
-                  properties: properties})
+        Ok(Layer {id: common.id, name: n, opacity: o.unwrap_or(1.0), visible: v.unwrap_or(true),
+                  locked: common.locked, tint_colour: common.tint_colour,
+                  offset_x: common.offset_x, offset_y: common.offset_y,
+                  parallax_x: common.parallax_x, parallax_y: common.parallax_y,
+                  tiles, chunks, properties, layer_index})
+    }
+
+    /// Returns the bounding box of the layer's tile data as `(x, y, width,
+    /// height)` in tiles. For infinite maps this is the union of all chunks, so
+    /// `x` and `y` can be negative.
+    pub fn bounds(&self) -> (i32, i32, u32, u32) {
+        if self.chunks.is_empty() {
+            let height = self.tiles.len() as u32;
+            let width = self.tiles.first().map_or(0, |row| row.len() as u32);
+            return (0, 0, width, height);
+        }
+        let min_x = self.chunks.iter().map(|c| c.x).min().unwrap();
+        let min_y = self.chunks.iter().map(|c| c.y).min().unwrap();
+        let max_x = self.chunks.iter().map(|c| c.x + c.width as i32).max().unwrap();
+        let max_y = self.chunks.iter().map(|c| c.y + c.height as i32).max().unwrap();
+        (min_x, min_y, (max_x - min_x) as u32, (max_y - min_y) as u32)
+    }
+
+    /// Returns the tile at the given tile coordinate, or `None` if there is no
+    /// tile data there. Coordinates may be negative for infinite maps.
+    pub fn get_tile(&self, x: i32, y: i32) -> Option<LayerTile> {
+        if self.chunks.is_empty() {
+            if x < 0 || y < 0 {
+                return None;
+            }
+            return self.tiles.get(y as usize).and_then(|row| row.get(x as usize)).cloned();
+        }
+        self.chunks.iter().filter_map(|c| c.get_tile(x, y)).next()
+    }
+
+    /// Replaces the tile at the given tile coordinate. Returns `false`, and
+    /// changes nothing, if the layer has no tile data there.
+    pub fn set_tile(&mut self, x: i32, y: i32, tile: LayerTile) -> bool {
+        let cell = if self.chunks.is_empty() {
+            if x < 0 || y < 0 {
+                return false;
+            }
+            self.tiles.get_mut(y as usize).and_then(|row| row.get_mut(x as usize))
+        } else {
+            self.chunks.iter_mut().filter_map(|c| c.tile_mut(x, y)).next()
+        };
+        match cell {
+            Some(cell) => {
+                *cell = tile;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// A rectangular piece of an infinite map's tile layer.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Chunk {
+    /// The x coordinate of the chunk's top left tile.
+    pub x: i32,
+    /// The y coordinate of the chunk's top left tile.
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    /// The tiles are arranged in rows, just like `Layer::tiles`.
+    pub tiles: Vec<Vec<LayerTile>>,
+}
+
+impl Chunk {
+    fn new<R: Read>(parser: &mut EventReader<R>, attrs: Vec<OwnedAttribute>, encoding: Option<String>, compression: Option<String>) -> Result<Chunk, TiledError> {
+        let ((), (x, y, w, h)) = get_attrs!(
+            attrs,
+            optionals: [],
+            required: [("x", x, |v:String| v.parse().ok()),
+                       ("y", y, |v:String| v.parse().ok()),
+                       ("width", width, |v:String| v.parse().ok()),
+                       ("height", height, |v:String| v.parse().ok())],
+            TiledError::MalformedAttributes("chunk must have an x, y, width and height with correct types".to_string()));
+        if w == 0 || h == 0 {
+            return Err(TiledError::MalformedAttributes("chunk must have a width and height above 0".to_string()));
+        }
+        let tiles = decode_data(parser, encoding, compression, w, "chunk")?;
+        Ok(Chunk {x, y, width: w, height: h, tiles})
+    }
+
+    /// Returns the tile at the given tile coordinate of the map, or `None` if
+    /// the coordinate is outside of this chunk.
+    pub fn get_tile(&self, x: i32, y: i32) -> Option<LayerTile> {
+        let (local_x, local_y) = (x - self.x, y - self.y);
+        if local_x < 0 || local_y < 0 || local_x >= self.width as i32 || local_y >= self.height as i32 {
+            return None;
+        }
+        self.tiles.get(local_y as usize).and_then(|row| row.get(local_x as usize)).cloned()
+    }
+
+    fn tile_mut(&mut self, x: i32, y: i32) -> Option<&mut LayerTile> {
+        let (local_x, local_y) = (x - self.x, y - self.y);
+        if local_x < 0 || local_y < 0 || local_x >= self.width as i32 || local_y >= self.height as i32 {
+            return None;
+        }
+        self.tiles.get_mut(local_y as usize).and_then(|row| row.get_mut(local_x as usize))
     }
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct ImageLayer {
+    /// The id Tiled gave the layer, unique within the map. 0 for maps saved
+    /// by versions of Tiled without layer ids.
+    pub id: u32,
     pub name: String,
     pub opacity: f32,
     pub visible: bool,
+    /// Whether the layer is locked for editing in Tiled.
+    pub locked: bool,
+    /// A colour multiplied with the layer's tiles, objects or image.
+    pub tint_colour: Option<Colour>,
+    /// The layer's offset in pixels.
     pub offset_x: f32,
     pub offset_y: f32,
+    /// How fast the layer moves relative to the camera, where 1.0 is the
+    /// speed of the map and 0.0 doesn't move at all.
+    pub parallax_x: f32,
+    pub parallax_y: f32,
     pub image: Option<Image>,
-    pub properties: Properties
+    /// Whether the image is repeated along the x axis.
+    pub repeat_x: bool,
+    /// Whether the image is repeated along the y axis.
+    pub repeat_y: bool,
+    pub properties: Properties,
+    /// The position of the layer amongst all the layers of its parent.
+    pub layer_index: u32,
 }
 
 impl ImageLayer {
-    fn new<R: Read>(parser: &mut EventReader<R>, attrs: Vec<OwnedAttribute>)
+    fn new<R: Read>(parser: &mut EventReader<R>, attrs: Vec<OwnedAttribute>, layer_index: u32)
                     -> Result<ImageLayer, TiledError> {
-        let ((o, v, ox, oy), n) = get_attrs!(
+        let ((o, v, rx, ry), n) = get_attrs!(
             attrs,
             optionals: [("opacity", opacity, |v:String| v.parse().ok()),
                         ("visible", visible, |v:String| v.parse().ok().map(|x:i32| x == 1)),
-                        ("offset_x", offset_x, |v:String| v.parse().ok()),
-                        ("offset_y", offset_y, |v:String| v.parse().ok())],
+                        ("repeatx", repeat_x, |v:String| Some(v == "1")),
+                        ("repeaty", repeat_y, |v:String| Some(v == "1"))],
             required: [("name", name, |v| Some(v))],
             TiledError::MalformedAttributes("layer must have a name".to_string()));
+        let common = LayerAttributes::new(&attrs)?;
         let mut properties = HashMap::new();
         let mut image: Option<Image> = None;
         parse_tag!(parser, "imagelayer",
@@ -569,30 +1933,395 @@ impl ImageLayer {
                        Ok(())
                    });
         Ok(ImageLayer {
+            id: common.id,
             name: n,
             opacity: o.unwrap_or(1.0),
             visible: v.unwrap_or(true),
-            offset_x: ox.unwrap_or(0.0),
-            offset_y: oy.unwrap_or(0.0),
+            locked: common.locked,
+            tint_colour: common.tint_colour,
+            offset_x: common.offset_x,
+            offset_y: common.offset_y,
+            parallax_x: common.parallax_x,
+            parallax_y: common.parallax_y,
             image,
+            repeat_x: rx.unwrap_or(false),
+            repeat_y: ry.unwrap_or(false),
             properties,
+            layer_index,
         })
     }
 }
 
 
 
+/// A group layer, which holds other layers, including other groups.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Group {
+    /// The id Tiled gave the layer, unique within the map. 0 for maps saved
+    /// by versions of Tiled without layer ids.
+    pub id: u32,
+    pub name: String,
+    pub opacity: f32,
+    pub visible: bool,
+    /// Whether the layer is locked for editing in Tiled.
+    pub locked: bool,
+    /// A colour multiplied with the tiles, objects and images of the layers in
+    /// the group.
+    pub tint_colour: Option<Colour>,
+    /// The layer's offset in pixels.
+    pub offset_x: f32,
+    pub offset_y: f32,
+    /// How fast the layer moves relative to the camera, where 1.0 is the
+    /// speed of the map and 0.0 doesn't move at all.
+    pub parallax_x: f32,
+    pub parallax_y: f32,
+    pub layers: Vec<Layer>,
+    pub image_layers: Vec<ImageLayer>,
+    pub object_groups: Vec<ObjectGroup>,
+    pub groups: Vec<Group>,
+    pub properties: Properties,
+    /// The position of the group amongst the layers of its parent.
+    pub layer_index: u32,
+}
+
+impl Group {
+    fn new<R: Read>(parser: &mut EventReader<R>, attrs: Vec<OwnedAttribute>, width: u32, infinite: bool, layer_index: u32,
+                    templates: &mut TemplateResolver) -> Result<Group, TiledError> {
+        let ((n, o, v), ()) = get_attrs!(
+            attrs,
+            optionals: [("name", name, Some),
+                        ("opacity", opacity, |v:String| v.parse().ok()),
+                        ("visible", visible, |v:String| v.parse().ok().map(|x:i32| x == 1))],
+            required: [],
+            TiledError::MalformedAttributes("group must have correctly typed attributes".to_string()));
+        let common = LayerAttributes::new(&attrs)?;
+        let mut layers = Vec::new();
+        let mut image_layers = Vec::new();
+        let mut object_groups = Vec::new();
+        let mut groups = Vec::new();
+        let mut properties = HashMap::new();
+        let mut child_index = 0;
+        parse_tag!(parser, "group",
+                   "layer" => |attrs| {
+                       layers.push(Layer::new(parser, attrs, width, infinite, child_index)?);
+                       child_index += 1;
+                       Ok(())
+                   },
+                   "imagelayer" => |attrs| {
+                       image_layers.push(ImageLayer::new(parser, attrs, child_index)?);
+                       child_index += 1;
+                       Ok(())
+                   },
+                   "objectgroup" => |attrs| {
+                       object_groups.push(ObjectGroup::new(parser, attrs, Some(child_index), templates)?);
+                       child_index += 1;
+                       Ok(())
+                   },
+                   "group" => |attrs| {
+                       groups.push(Group::new(parser, attrs, width, infinite, child_index, templates)?);
+                       child_index += 1;
+                       Ok(())
+                   },
+                   "properties" => |_| {
+                       properties = parse_properties(parser)?;
+                       Ok(())
+                   });
+        Ok(Group {
+            id: common.id,
+            name: n.unwrap_or_default(),
+            opacity: o.unwrap_or(1.0),
+            visible: v.unwrap_or(true),
+            locked: common.locked,
+            tint_colour: common.tint_colour,
+            offset_x: common.offset_x,
+            offset_y: common.offset_y,
+            parallax_x: common.parallax_x,
+            parallax_y: common.parallax_y,
+            layers,
+            image_layers,
+            object_groups,
+            groups,
+            properties,
+            layer_index,
+        })
+    }
+
+    /// Returns the layers directly inside this group in the order they appear
+    /// in the file, which is also the order they should be drawn in.
+    pub fn ordered_layers(&self) -> Vec<LayerRef<'_>> {
+        ordered_layers(&self.layers, &self.image_layers, &self.object_groups, &self.groups)
+    }
+
+    /// Returns every layer nested in this group in drawing order, with the
+    /// opacity, visibility, offset and parallax factors of this group and its
+    /// subgroups combined into them.
+    pub fn flatten_layers(&self) -> Vec<FlattenedLayer<'_>> {
+        let mut flattened = Vec::new();
+        let root = GroupState::root().nest(self);
+        flatten_into(&mut flattened, &root, &self.layers, &self.image_layers, &self.object_groups, &self.groups);
+        flattened
+    }
+}
+
+/// A reference to a layer of any kind.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum LayerRef<'a> {
+    Tile(&'a Layer),
+    Image(&'a ImageLayer),
+    Object(&'a ObjectGroup),
+    Group(&'a Group),
+}
+
+impl<'a> LayerRef<'a> {
+    /// The position of the layer amongst all the layers of its parent.
+    pub fn layer_index(&self) -> u32 {
+        match *self {
+            LayerRef::Tile(layer) => layer.layer_index,
+            LayerRef::Image(layer) => layer.layer_index,
+            LayerRef::Object(group) => group.layer_index.unwrap_or(0),
+            LayerRef::Group(group) => group.layer_index,
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        match *self {
+            LayerRef::Tile(layer) => layer.id,
+            LayerRef::Image(layer) => layer.id,
+            LayerRef::Object(group) => group.id,
+            LayerRef::Group(group) => group.id,
+        }
+    }
+
+    pub fn name(&self) -> &'a str {
+        match *self {
+            LayerRef::Tile(layer) => &layer.name,
+            LayerRef::Image(layer) => &layer.name,
+            LayerRef::Object(group) => &group.name,
+            LayerRef::Group(group) => &group.name,
+        }
+    }
+
+    pub fn opacity(&self) -> f32 {
+        match *self {
+            LayerRef::Tile(layer) => layer.opacity,
+            LayerRef::Image(layer) => layer.opacity,
+            LayerRef::Object(group) => group.opacity,
+            LayerRef::Group(group) => group.opacity,
+        }
+    }
+
+    pub fn visible(&self) -> bool {
+        match *self {
+            LayerRef::Tile(layer) => layer.visible,
+            LayerRef::Image(layer) => layer.visible,
+            LayerRef::Object(group) => group.visible,
+            LayerRef::Group(group) => group.visible,
+        }
+    }
+
+    pub fn locked(&self) -> bool {
+        match *self {
+            LayerRef::Tile(layer) => layer.locked,
+            LayerRef::Image(layer) => layer.locked,
+            LayerRef::Object(group) => group.locked,
+            LayerRef::Group(group) => group.locked,
+        }
+    }
+
+    pub fn tint_colour(&self) -> Option<Colour> {
+        match *self {
+            LayerRef::Tile(layer) => layer.tint_colour,
+            LayerRef::Image(layer) => layer.tint_colour,
+            LayerRef::Object(group) => group.tint_colour,
+            LayerRef::Group(group) => group.tint_colour,
+        }
+    }
+
+    /// The layer's own offset as `(x, y)`, without the offsets of its parent
+    /// groups.
+    pub fn offset(&self) -> (f32, f32) {
+        match *self {
+            LayerRef::Tile(layer) => (layer.offset_x, layer.offset_y),
+            LayerRef::Image(layer) => (layer.offset_x, layer.offset_y),
+            LayerRef::Object(group) => (group.offset_x, group.offset_y),
+            LayerRef::Group(group) => (group.offset_x, group.offset_y),
+        }
+    }
+
+    /// The layer's own parallax factors as `(x, y)`.
+    pub fn parallax(&self) -> (f32, f32) {
+        match *self {
+            LayerRef::Tile(layer) => (layer.parallax_x, layer.parallax_y),
+            LayerRef::Image(layer) => (layer.parallax_x, layer.parallax_y),
+            LayerRef::Object(group) => (group.parallax_x, group.parallax_y),
+            LayerRef::Group(group) => (group.parallax_x, group.parallax_y),
+        }
+    }
+}
+
+// The attributes shared by all kinds of layers, apart from the name, opacity
+// and visibility, which each layer reads itself.
+struct LayerAttributes {
+    id: u32,
+    locked: bool,
+    tint_colour: Option<Colour>,
+    offset_x: f32,
+    offset_y: f32,
+    parallax_x: f32,
+    parallax_y: f32,
+}
+
+impl LayerAttributes {
+    fn new(attrs: &[OwnedAttribute]) -> Result<LayerAttributes, TiledError> {
+        let ((id, locked, tint_colour, offset_x, offset_y, parallax_x, parallax_y), ()) = get_attrs!(
+            attrs,
+            optionals: [("id", id, |v:String| v.parse().ok()),
+                        ("locked", locked, |v:String| Some(v == "1")),
+                        ("tintcolor", tint_colour, |v:String| v.parse().ok()),
+                        ("offsetx", offset_x, |v:String| v.parse().ok()),
+                        ("offsety", offset_y, |v:String| v.parse().ok()),
+                        ("parallaxx", parallax_x, |v:String| v.parse().ok()),
+                        ("parallaxy", parallax_y, |v:String| v.parse().ok())],
+            required: [],
+            TiledError::MalformedAttributes("layer must have correctly typed attributes".to_string()));
+        Ok(LayerAttributes {
+            id: id.unwrap_or(0),
+            locked: locked.unwrap_or(false),
+            tint_colour,
+            offset_x: offset_x.unwrap_or(0.0),
+            offset_y: offset_y.unwrap_or(0.0),
+            parallax_x: parallax_x.unwrap_or(1.0),
+            parallax_y: parallax_y.unwrap_or(1.0),
+        })
+    }
+}
+
+fn ordered_layers<'a>(layers: &'a [Layer], image_layers: &'a [ImageLayer],
+                      object_groups: &'a [ObjectGroup], groups: &'a [Group]) -> Vec<LayerRef<'a>> {
+    let mut ordered: Vec<LayerRef<'a>> = layers.iter().map(LayerRef::Tile)
+        .chain(image_layers.iter().map(LayerRef::Image))
+        .chain(object_groups.iter().map(LayerRef::Object))
+        .chain(groups.iter().map(LayerRef::Group))
+        .collect();
+    ordered.sort_by_key(|layer| layer.layer_index());
+    ordered
+}
+
+/// A layer taken out of a group tree by `Map::flatten_layers`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct FlattenedLayer<'a> {
+    /// The layer itself. This is never a `LayerRef::Group`.
+    pub layer: LayerRef<'a>,
+    /// The layer's opacity multiplied by the opacity of all its parent groups.
+    pub opacity: f32,
+    /// Whether the layer and all its parent groups are visible.
+    pub visible: bool,
+    /// The layer's offset plus the offsets of all its parent groups.
+    pub offset_x: f32,
+    pub offset_y: f32,
+    /// The layer's parallax factors multiplied by those of all its parent
+    /// groups.
+    pub parallax_x: f32,
+    pub parallax_y: f32,
+}
+
+impl<'a> FlattenedLayer<'a> {
+    /// Returns where the layer's origin should be drawn, relative to the map's
+    /// origin, when the camera is centered on `(camera_x, camera_y)` in map
+    /// pixels. This is the layer's offset plus the shift caused by its
+    /// parallax factors, the same way Tiled computes it.
+    pub fn screen_offset(&self, map: &Map, camera_x: f32, camera_y: f32) -> (f32, f32) {
+        (self.offset_x + (1.0 - self.parallax_x) * (camera_x - map.parallax_origin_x),
+         self.offset_y + (1.0 - self.parallax_y) * (camera_y - map.parallax_origin_y))
+    }
+}
+
+// The combined opacity, visibility, offset and parallax of the groups above a
+// layer.
+#[derive(Clone, Copy)]
+struct GroupState {
+    opacity: f32,
+    visible: bool,
+    offset_x: f32,
+    offset_y: f32,
+    parallax_x: f32,
+    parallax_y: f32,
+}
+
+impl GroupState {
+    fn root() -> GroupState {
+        GroupState {opacity: 1.0, visible: true, offset_x: 0.0, offset_y: 0.0, parallax_x: 1.0, parallax_y: 1.0}
+    }
+
+    fn nest(&self, group: &Group) -> GroupState {
+        GroupState {
+            opacity: self.opacity * group.opacity,
+            visible: self.visible && group.visible,
+            offset_x: self.offset_x + group.offset_x,
+            offset_y: self.offset_y + group.offset_y,
+            parallax_x: self.parallax_x * group.parallax_x,
+            parallax_y: self.parallax_y * group.parallax_y,
+        }
+    }
+
+    fn apply<'a>(&self, layer: LayerRef<'a>) -> FlattenedLayer<'a> {
+        let (offset_x, offset_y) = layer.offset();
+        let (parallax_x, parallax_y) = layer.parallax();
+        FlattenedLayer {
+            layer,
+            opacity: self.opacity * layer.opacity(),
+            visible: self.visible && layer.visible(),
+            offset_x: self.offset_x + offset_x,
+            offset_y: self.offset_y + offset_y,
+            parallax_x: self.parallax_x * parallax_x,
+            parallax_y: self.parallax_y * parallax_y,
+        }
+    }
+}
+
+fn flatten_into<'a>(flattened: &mut Vec<FlattenedLayer<'a>>, parent: &GroupState,
+                    layers: &'a [Layer], image_layers: &'a [ImageLayer],
+                    object_groups: &'a [ObjectGroup], groups: &'a [Group]) {
+    for layer in ordered_layers(layers, image_layers, object_groups, groups) {
+        match layer {
+            LayerRef::Group(g) => {
+                let nested = parent.nest(g);
+                flatten_into(flattened, &nested, &g.layers, &g.image_layers, &g.object_groups, &g.groups);
+            }
+            _ => flattened.push(parent.apply(layer)),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct ObjectGroup {
+    /// The id Tiled gave the layer, unique within the map. 0 for maps saved
+    /// by versions of Tiled without layer ids.
+    pub id: u32,
     pub name: String,
     pub opacity: f32,
     pub visible: bool,
+    /// Whether the layer is locked for editing in Tiled.
+    pub locked: bool,
+    /// A colour multiplied with the layer's tiles, objects or image.
+    pub tint_colour: Option<Colour>,
+    /// The layer's offset in pixels.
+    pub offset_x: f32,
+    pub offset_y: f32,
+    /// How fast the layer moves relative to the camera, where 1.0 is the
+    /// speed of the map and 0.0 doesn't move at all.
+    pub parallax_x: f32,
+    pub parallax_y: f32,
     pub objects: Vec<Object>,
     pub colour: Option<Colour>,
+    /// The position of the layer amongst all the layers of its parent. This is
+    /// `None` for the collision shapes of a `Tile`, which aren't a layer.
+    pub layer_index: Option<u32>,
 }
 
 impl ObjectGroup {
-    fn new<R: Read>(parser: &mut EventReader<R>, attrs: Vec<OwnedAttribute>) -> Result<ObjectGroup, TiledError> {
+    fn new<R: Read>(parser: &mut EventReader<R>, attrs: Vec<OwnedAttribute>, layer_index: Option<u32>,
+                    templates: &mut TemplateResolver) -> Result<ObjectGroup, TiledError> {
         let ((o, v, c, n), ()) = get_attrs!(
             attrs,
             optionals: [("opacity", opacity, |v:String| v.parse().ok()),
@@ -601,16 +2330,21 @@ impl ObjectGroup {
                         ("name", name, |v:String| v.into())],
             required: [],
             TiledError::MalformedAttributes("object groups must have a name".to_string()));
+        let common = LayerAttributes::new(&attrs)?;
         let mut objects = Vec::new();
         parse_tag!(parser, "objectgroup",
                    "object" => |attrs| {
-                        objects.push(try!(Object::new(parser, attrs)));
+                        objects.push(Object::new(parser, attrs, templates)?);
                         Ok(())
                    });
-        Ok(ObjectGroup {name: n.unwrap_or(String::new()),
+        Ok(ObjectGroup {id: common.id, name: n.unwrap_or(String::new()),
                         opacity: o.unwrap_or(1.0), visible: v.unwrap_or(true),
+                        locked: common.locked, tint_colour: common.tint_colour,
+                        offset_x: common.offset_x, offset_y: common.offset_y,
+                        parallax_x: common.parallax_x, parallax_y: common.parallax_y,
                         objects: objects,
-                        colour: c})
+                        colour: c,
+                        layer_index})
     }
 }
 
@@ -630,12 +2364,36 @@ pub enum ObjectShape {
     Polygon {
         points: Vec<(f32, f32)>,
     },
+    /// A single point at the object's position.
+    Point,
+    /// Text drawn inside the `width` by `height` box of the object.
+    Text {
+        width: f32,
+        height: f32,
+        text: String,
+        font_family: String,
+        pixel_size: u32,
+        /// Whether the text is wrapped to the width of the object.
+        wrap: bool,
+        colour: Colour,
+        bold: bool,
+        italic: bool,
+        underline: bool,
+        strikeout: bool,
+        kerning: bool,
+        halign: HorizontalAlignment,
+        valign: VerticalAlignment,
+    },
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Object {
     pub id: u32,
-    pub gid: u32,
+    /// The template the object was created from. The template's values have
+    /// already been merged into the object's fields.
+    pub template: Option<Arc<Template>>,
+    /// The tile shown by the object, for tile objects.
+    pub tile: Option<LayerTile>,
     pub name: String,
     pub obj_type: String,
     pub x: f32,
@@ -647,8 +2405,8 @@ pub struct Object {
 }
 
 impl Object {
-    fn new<R: Read>(parser: &mut EventReader<R>, attrs: Vec<OwnedAttribute>) -> Result<Object, TiledError> {
-        let ((id,gid,n,t,w, h, v, r), (x, y)) = get_attrs!(
+    fn new<R: Read>(parser: &mut EventReader<R>, attrs: Vec<OwnedAttribute>, templates: &mut TemplateResolver) -> Result<Object, TiledError> {
+        let ((id, gid, n, t, w, h, v, r, x, y, template), ()) = get_attrs!(
             attrs,
             optionals: [("id", id, |v:String| v.parse().ok()),
                         ("gid", gid, |v:String| v.parse().ok()),
@@ -656,19 +2414,13 @@ impl Object {
                         ("type", obj_type, |v:String| v.parse().ok()),
                         ("width", width, |v:String| v.parse().ok()),
                         ("height", height, |v:String| v.parse().ok()),
-                        ("visible", visible, |v:String| v.parse().ok()),
-                        ("rotation", rotation, |v:String| v.parse().ok())],
-            required: [("x", x, |v:String| v.parse().ok()),
-                       ("y", y, |v:String| v.parse().ok())],
+                        ("visible", visible, |v:String| v.parse().ok().map(|x:i32| x == 1)),
+                        ("rotation", rotation, |v:String| v.parse().ok()),
+                        ("x", x, |v:String| v.parse().ok()),
+                        ("y", y, |v:String| v.parse().ok()),
+                        ("template", template, Some)],
+            required: [],
             TiledError::MalformedAttributes("objects must have an x and a y number".to_string()));
-        let v = v.unwrap_or(true);
-        let w = w.unwrap_or(0f32);
-        let h = h.unwrap_or(0f32);
-        let r = r.unwrap_or(0f32);
-        let id = id.unwrap_or(0u32);
-        let gid = gid.unwrap_or(0u32);
-        let n = n.unwrap_or(String::new());
-        let t = t.unwrap_or(String::new());
         let mut shape = None;
         let mut properties = HashMap::new();
 
@@ -676,8 +2428,8 @@ impl Object {
             parser, "object",
             "ellipse" => |_| {
                 shape = Some(ObjectShape::Ellipse {
-                    width: w,
-                    height: h,
+                    width: 0.0,
+                    height: 0.0,
                 });
                 Ok(())
             },
@@ -689,28 +2441,113 @@ impl Object {
                 shape = Some(try!(Object::new_polygon(attrs)));
                 Ok(())
             },
+            "point" => |_| {
+                shape = Some(ObjectShape::Point);
+                Ok(())
+            },
+            "text" => |attrs| {
+                shape = Some(Object::new_text(parser, attrs)?);
+                Ok(())
+            },
             "properties" => |_| {
                 properties = try!(parse_properties(parser));
                 Ok(())
             }
         );
 
-        let shape = shape.unwrap_or(ObjectShape::Rect {
-            width: w,
-            height: h,
-        });
-
-        Ok(Object {
-            id: id,
-            gid: gid,
-            name: n.clone(),
-            obj_type: t.clone(),
-            x: x,
-            y: y,
-            rotation: r,
-            visible: v,
-            shape: shape,
-            properties: properties,
+        let template = match template {
+            Some(source) => Some(templates(&source)?),
+            None => None,
+        };
+        let attrs = ObjectAttributes {
+            id, gid, name: n, obj_type: t, x, y, width: w, height: h, rotation: r, visible: v, shape, properties,
+        };
+        Ok(attrs.build(template))
+    }
+
+    /// Returns the tileset of a tile object.
+    pub fn tileset<'a>(&self, map: &'a Map) -> Option<&'a MapTileset> {
+        self.tile.and_then(|tile| tile.tileset(map))
+    }
+
+    /// Returns the data the tileset stores for the tile of a tile object.
+    pub fn tile_data<'a>(&self, map: &'a Map) -> Option<&'a Tile> {
+        self.tile.and_then(|tile| tile.tile_data(map))
+    }
+
+    /// Returns the `(x, y, width, height)` of the object's box before
+    /// rotation, with `x` and `y` at its top left corner. The object rotates
+    /// around its position.
+    ///
+    /// Tile objects are anchored by their tileset's `object_alignment`,
+    /// which is the bottom left corner unless the tileset says otherwise.
+    /// A tile object without a size has the size of its tile. Other objects
+    /// are anchored at the top left. Points and polygons are not resized, so
+    /// their box is their position.
+    pub fn bounds(&self, map: &Map) -> (f32, f32, f32, f32) {
+        let (width, height) = match self.shape {
+            ObjectShape::Rect {width, height} | ObjectShape::Ellipse {width, height} |
+            ObjectShape::Text {width, height, ..} => (width, height),
+            ObjectShape::Polyline {..} | ObjectShape::Polygon {..} | ObjectShape::Point => (0.0, 0.0),
+        };
+        let (tile, tileset) = match self.tile.and_then(|tile| tile.tileset(map).map(|tileset| (tile, tileset))) {
+            Some(found) => found,
+            None => return (self.x, self.y, width, height),
+        };
+        let (width, height) = if width == 0.0 && height == 0.0 {
+            let (w, h) = tileset.tile_size(tile.gid - tileset.first_gid);
+            (w as f32, h as f32)
+        } else {
+            (width, height)
+        };
+        let (origin_x, origin_y) = tileset.object_alignment.origin(map.orientation);
+        (self.x - origin_x * width, self.y - origin_y * height, width, height)
+    }
+
+    // The size of a text object is filled in from the object's width and
+    // height, like for ellipses.
+    fn new_text<R: Read>(parser: &mut EventReader<R>, attrs: Vec<OwnedAttribute>) -> Result<ObjectShape, TiledError> {
+        let ((font_family, pixel_size, wrap, colour, bold, italic, underline, strikeout, kerning, halign, valign), ()) = get_attrs!(
+            attrs,
+            optionals: [("fontfamily", font_family, Some),
+                        ("pixelsize", pixel_size, |v:String| v.parse().ok()),
+                        ("wrap", wrap, |v:String| Some(v == "1")),
+                        ("color", colour, |v:String| v.parse().ok()),
+                        ("bold", bold, |v:String| Some(v == "1")),
+                        ("italic", italic, |v:String| Some(v == "1")),
+                        ("underline", underline, |v:String| Some(v == "1")),
+                        ("strikeout", strikeout, |v:String| Some(v == "1")),
+                        ("kerning", kerning, |v:String| Some(v == "1")),
+                        ("halign", halign, |v:String| v.parse().ok()),
+                        ("valign", valign, |v:String| v.parse().ok())],
+            required: [],
+            TiledError::MalformedAttributes("text has malformed attributes".to_string()));
+
+        let mut text = String::new();
+        loop {
+            match parser.next().map_err(TiledError::XmlDecodingError)? {
+                XmlEvent::Characters(s) | XmlEvent::Whitespace(s) | XmlEvent::CData(s) => text.push_str(&s),
+                XmlEvent::EndElement {ref name} if name.local_name == "text" => break,
+                XmlEvent::EndDocument => return Err(TiledError::PrematureEnd("Document ended before we expected.".to_string())),
+                _ => {}
+            }
+        }
+
+        Ok(ObjectShape::Text {
+            width: 0.0,
+            height: 0.0,
+            text,
+            font_family: font_family.unwrap_or_else(|| "sans-serif".to_string()),
+            pixel_size: pixel_size.unwrap_or(16),
+            wrap: wrap.unwrap_or(false),
+            colour: colour.unwrap_or(Colour {red: 0, green: 0, blue: 0, alpha: 255}),
+            bold: bold.unwrap_or(false),
+            italic: italic.unwrap_or(false),
+            underline: underline.unwrap_or(false),
+            strikeout: strikeout.unwrap_or(false),
+            kerning: kerning.unwrap_or(true),
+            halign: halign.unwrap_or(HorizontalAlignment::Left),
+            valign: valign.unwrap_or(VerticalAlignment::Top),
         })
     }
 
@@ -756,10 +2593,130 @@ impl Object {
     }
 }
 
+/// An object template loaded from a `.tx` (or JSON `.tj`) file. Templates
+/// are shared between the objects using them and cached by the `Loader`.
 #[derive(Debug, PartialEq, Clone)]
+pub struct Template {
+    /// The path of the template file.
+    pub source: PathBuf,
+    /// The tileset of the template's object if it is a tile object. The
+    /// `first_gid` is the one used inside the template file.
+    pub tileset: Option<MapTileset>,
+    /// The object as defined by the template, with GIDs relative to
+    /// `tileset`.
+    pub object: Object,
+}
+
+impl Template {
+    fn new<R: Read, RR: ResourceReader>(file: R, path: &Path, loader: &mut Loader<RR>) -> Result<Template, TiledError> {
+        let mut parser = EventReader::new(file);
+        loop {
+            match parser.next().map_err(TiledError::XmlDecodingError)? {
+                XmlEvent::StartElement {ref name, ..} if name.local_name == "template" => break,
+                XmlEvent::EndDocument => return Err(TiledError::PrematureEnd("Template document ended before template was parsed".to_string())),
+                _ => {}
+            }
+        }
+
+        let mut tileset = None;
+        let mut object = None;
+        parse_tag!(parser, "template",
+                   "tileset" => |attrs| {
+                       tileset = Some(Tileset::parse_map_tileset(&mut parser, attrs, Some(path), loader)?);
+                       Ok(())
+                   },
+                   "object" => |attrs| {
+                       object = Some(Object::new(&mut parser, attrs, &mut no_templates)?);
+                       Ok(())
+                   });
+        let object = object.ok_or_else(|| TiledError::Other(format!("Template {:?} has no object", path)))?;
+        Ok(Template {source: path.to_path_buf(), tileset, object})
+    }
+}
+
+// Looks up the template an object refers to, giving the template and its
+// tile with the GID translated to the map being parsed.
+type TemplateResolver<'a> = dyn FnMut(&str) -> Result<(Arc<Template>, Option<LayerTile>), TiledError> + 'a;
+
+// Used for objects which can't come from a template, like the collision
+// shapes of tiles and the objects of templates themselves.
+fn no_templates(source: &str) -> Result<(Arc<Template>, Option<LayerTile>), TiledError> {
+    Err(TiledError::Other(format!("Template {:?} used where templates aren't supported", source)))
+}
+
+// The attributes of an object as given in the file. Anything missing is taken
+// from the object's template, if there is one, and otherwise defaults.
+struct ObjectAttributes {
+    id: Option<u32>,
+    gid: Option<u32>,
+    name: Option<String>,
+    obj_type: Option<String>,
+    x: Option<f32>,
+    y: Option<f32>,
+    width: Option<f32>,
+    height: Option<f32>,
+    rotation: Option<f32>,
+    visible: Option<bool>,
+    /// Ellipses and text only say which shape the object has, their size
+    /// comes from `width` and `height`.
+    shape: Option<ObjectShape>,
+    properties: Properties,
+}
+
+impl ObjectAttributes {
+    fn build(self, template: Option<(Arc<Template>, Option<LayerTile>)>) -> Object {
+        let (template, template_tile) = match template {
+            Some((template, tile)) => (Some(template), tile),
+            None => (None, None),
+        };
+        let base = template.as_ref().map(|t| &t.object);
+        let (base_width, base_height) = match base.map(|b| &b.shape) {
+            Some(&ObjectShape::Rect {width, height}) | Some(&ObjectShape::Ellipse {width, height}) |
+            Some(&ObjectShape::Text {width, height, ..}) => (width, height),
+            _ => (0.0, 0.0),
+        };
+        let width = self.width.unwrap_or(base_width);
+        let height = self.height.unwrap_or(base_height);
+        let shape = match self.shape.as_ref().or_else(|| base.map(|b| &b.shape)) {
+            Some(&ObjectShape::Ellipse {..}) => ObjectShape::Ellipse {width, height},
+            Some(&ObjectShape::Rect {..}) | None => ObjectShape::Rect {width, height},
+            Some(&ObjectShape::Text {ref text, ref font_family, pixel_size, wrap, colour, bold, italic, underline,
+                                     strikeout, kerning, halign, valign, ..}) => {
+                ObjectShape::Text {width, height, text: text.clone(), font_family: font_family.clone(), pixel_size,
+                                   wrap, colour, bold, italic, underline, strikeout, kerning, halign, valign}
+            }
+            Some(shape) => shape.clone(),
+        };
+        let mut properties = base.map_or_else(HashMap::new, |b| b.properties.clone());
+        properties.extend(self.properties);
+
+        Object {
+            id: self.id.unwrap_or(0),
+            tile: match self.gid {
+                Some(gid) => Some(LayerTile::new(gid)).filter(|tile| !tile.is_empty()),
+                None => template_tile,
+            },
+            name: self.name.or_else(|| base.map(|b| b.name.clone())).unwrap_or_default(),
+            obj_type: self.obj_type.or_else(|| base.map(|b| b.obj_type.clone())).unwrap_or_default(),
+            x: self.x.unwrap_or(0.0),
+            y: self.y.unwrap_or(0.0),
+            rotation: self.rotation.or_else(|| base.map(|b| b.rotation)).unwrap_or(0.0),
+            visible: self.visible.or_else(|| base.map(|b| b.visible)).unwrap_or(true),
+            shape,
+            properties,
+            template,
+        }
+    }
+}
+
+/// One frame of a tile's animation.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub struct Frame {
-    tile_id: u32,
-    duration: u32,
+    /// The id of the tile shown during the frame, in the same tileset as the
+    /// animated tile.
+    pub tile_id: u32,
+    /// How long the frame is shown, in milliseconds.
+    pub duration: u32,
 }
 
 impl Frame {
@@ -770,13 +2727,30 @@ impl Frame {
             required: [("tileid", tile_id, |v:String| v.parse().ok()),
             ("duration", duration, |v:String| v.parse().ok())],
             TiledError::MalformedAttributes("A frame must have tileid and duration".to_string()));
-        Ok(Frame {
-            tile_id: tile_id,
-            duration: duration,
-        })
+        Ok(Frame {tile_id, duration})
     }
 }
 
+fn parse_terrain_types<R: Read>(parser: &mut EventReader<R>) -> Result<Vec<Terrain>, TiledError> {
+    let mut terrains = Vec::new();
+    parse_tag!(parser, "terraintypes",
+               "terrain" => |attrs| {
+                    terrains.push(Terrain::new(parser, attrs)?);
+                    Ok(())
+               });
+    Ok(terrains)
+}
+
+fn parse_wang_sets<R: Read>(parser: &mut EventReader<R>) -> Result<Vec<WangSet>, TiledError> {
+    let mut wang_sets = Vec::new();
+    parse_tag!(parser, "wangsets",
+               "wangset" => |attrs| {
+                    wang_sets.push(WangSet::new(parser, attrs)?);
+                    Ok(())
+               });
+    Ok(wang_sets)
+}
+
 fn parse_animation<R: Read>(parser: &mut EventReader<R>) -> Result<Vec<Frame>, TiledError> {
     let mut animation = Vec::new();
     parse_tag!(parser, "animation",
@@ -787,7 +2761,7 @@ fn parse_animation<R: Read>(parser: &mut EventReader<R>) -> Result<Vec<Frame>, T
     Ok(animation)
 }
 
-fn parse_data<R: Read>(parser: &mut EventReader<R>, attrs: Vec<OwnedAttribute>, width: u32) -> Result<Vec<Vec<u32>>, TiledError> {
+fn parse_data<R: Read>(parser: &mut EventReader<R>, attrs: Vec<OwnedAttribute>, width: u32) -> Result<Vec<Vec<LayerTile>>, TiledError> {
     let ((e, c), ()) = get_attrs!(
         attrs,
         optionals: [("encoding", encoding, |v| Some(v)),
@@ -795,31 +2769,59 @@ fn parse_data<R: Read>(parser: &mut EventReader<R>, attrs: Vec<OwnedAttribute>,
         required: [],
         TiledError::MalformedAttributes("data must have an encoding and a compression".to_string()));
 
-    match (e,c) {
-        (None,None) => return Err(TiledError::Other("XML format is currently not supported".to_string())),
+    decode_data(parser, e, c, width, "data")
+}
+
+fn parse_infinite_data<R: Read>(parser: &mut EventReader<R>, attrs: Vec<OwnedAttribute>) -> Result<Vec<Chunk>, TiledError> {
+    let ((e, c), ()) = get_attrs!(
+        attrs,
+        optionals: [("encoding", encoding, Some),
+                   ("compression", compression, Some)],
+        required: [],
+        TiledError::MalformedAttributes("data must have an encoding and a compression".to_string()));
+
+    let mut chunks = Vec::new();
+    parse_tag!(parser, "data",
+               "chunk" => |attrs| {
+                    chunks.push(Chunk::new(parser, attrs, e.clone(), c.clone())?);
+                    Ok(())
+               });
+    Ok(chunks)
+}
+
+// Decodes the tile data up until `close_tag`, which is either the layer's
+// `data` element or one of its `chunk`s.
+fn decode_data<R: Read>(parser: &mut EventReader<R>, e: Option<String>, c: Option<String>, width: u32, close_tag: &str) -> Result<Vec<Vec<LayerTile>>, TiledError> {
+    let rows = match (e,c) {
+        (None,None) => decode_xml(parser, width, close_tag),
         (Some(e),None) =>
             match e.as_ref() {
-                "base64" => return parse_base64(parser).map(|v| convert_to_u32(&v,width)),
-                "csv" => return decode_csv(parser),
-                e => return Err(TiledError::Other(format!("Unknown encoding format {}",e))),
+                "base64" => parse_base64(parser, close_tag).and_then(|v| convert_to_u32(&v,width)),
+                "csv" => decode_csv(parser, close_tag),
+                e => Err(TiledError::Other(format!("Unknown encoding format {}",e))),
             },
         (Some(e),Some(c)) =>
             match (e.as_ref(),c.as_ref()) {
-                ("base64","zlib") => return parse_base64(parser).and_then(decode_zlib).map(|v| convert_to_u32(&v,width) ),
-                ("base64","gzip") => return parse_base64(parser).and_then(decode_gzip).map(|v| convert_to_u32(&v,width)),
-                (e,c) => return Err(TiledError::Other(format!("Unknown combination of {} encoding and {} compression",e,c)))
+                ("base64","zlib") => parse_base64(parser, close_tag).and_then(decode_zlib).and_then(|v| convert_to_u32(&v,width)),
+                ("base64","gzip") => parse_base64(parser, close_tag).and_then(decode_gzip).and_then(|v| convert_to_u32(&v,width)),
+                #[cfg(feature = "zstd")]
+                ("base64","zstd") => parse_base64(parser, close_tag).and_then(decode_zstd).and_then(|v| convert_to_u32(&v,width)),
+                #[cfg(not(feature = "zstd"))]
+                ("base64","zstd") => Err(TiledError::Other("zstd compression requires the \"zstd\" feature".to_string())),
+                (e,c) => Err(TiledError::Other(format!("Unknown combination of {} encoding and {} compression",e,c)))
             },
-        _ => return Err(TiledError::Other("Missing encoding format".to_string())),
-    };
+        _ => Err(TiledError::Other("Missing encoding format".to_string())),
+    }?;
+    Ok(rows.into_iter().map(|row| row.into_iter().map(LayerTile::new).collect()).collect())
 }
 
-fn parse_base64<R: Read>(parser: &mut EventReader<R>) -> Result<Vec<u8>, TiledError> {
+fn parse_base64<R: Read>(parser: &mut EventReader<R>, close_tag: &str) -> Result<Vec<u8>, TiledError> {
     loop {
         match try!(parser.next().map_err(TiledError::XmlDecodingError)) {
             XmlEvent::Characters(s) => return decode_base64(s.trim().as_bytes())
                                     .map_err(TiledError::Base64DecodingError),
             XmlEvent::EndElement {name, ..} => {
-                if name.local_name == "data" {
+                if name.local_name == close_tag {
                     return Ok(Vec::new());
                 }
             }
@@ -845,7 +2847,12 @@ fn decode_gzip(data: Vec<u8>) -> Result<Vec<u8>, TiledError> {
     Ok(data)
 }
 
-fn decode_csv<R: Read>(parser: &mut EventReader<R>) -> Result<Vec<Vec<u32>>, TiledError> {
+#[cfg(feature = "zstd")]
+fn decode_zstd(data: Vec<u8>) -> Result<Vec<u8>, TiledError> {
+    zstd::stream::decode_all(&data[..]).map_err(TiledError::DecompressingError)
+}
+
+fn decode_csv<R: Read>(parser: &mut EventReader<R>, close_tag: &str) -> Result<Vec<Vec<u32>>, TiledError> {
     loop {
         match try!(parser.next().map_err(TiledError::XmlDecodingError)) {
             XmlEvent::Characters(s) => {
@@ -854,12 +2861,15 @@ fn decode_csv<R: Read>(parser: &mut EventReader<R>) -> Result<Vec<Vec<u32>>, Til
                     if row.trim() == "" {
                         continue;
                     }
-                    rows.push(row.split(',').filter(|v| v.trim() != "").map(|v| v.replace('\r', "").parse().unwrap()).collect());
+                    let gids = row.split(',').filter(|v| v.trim() != "")
+                        .map(|v| v.trim().parse().map_err(|_| TiledError::Other(format!("Invalid gid {:?} in csv data", v.trim()))))
+                        .collect::<Result<Vec<u32>, TiledError>>()?;
+                    rows.push(gids);
                 }
                 return Ok(rows);
             }
             XmlEvent::EndElement {name, ..} => {
-                if name.local_name == "data" {
+                if name.local_name == close_tag {
                     return Ok(Vec::new());
                 }
             }
@@ -868,9 +2878,38 @@ fn decode_csv<R: Read>(parser: &mut EventReader<R>) -> Result<Vec<Vec<u32>>, Til
     }
 }
 
-fn convert_to_u32(all: &Vec<u8>, width: u32) -> Vec<Vec<u32>> {
+fn decode_xml<R: Read>(parser: &mut EventReader<R>, width: u32, close_tag: &str) -> Result<Vec<Vec<u32>>, TiledError> {
+    let mut gids = Vec::new();
+    parse_tag!(parser, close_tag,
+               "tile" => |attrs: Vec<OwnedAttribute>| {
+                    let (gid, ()) = get_attrs!(
+                        attrs,
+                        optionals: [("gid", gid, |v:String| v.parse().ok())],
+                        required: [],
+                        TiledError::MalformedAttributes("tile must have a gid with the correct type".to_string()));
+                    gids.push(gid.unwrap_or(0));
+                    Ok(())
+               });
+    if width == 0 {
+        return Ok(Vec::new());
+    }
+    Ok(gids.chunks(width as usize).map(|row| row.to_vec()).collect())
+}
+
+// Reads the little-endian gids of decoded base64 data, `width` to a row.
+fn convert_to_u32(all: &[u8], width: u32) -> Result<Vec<Vec<u32>>, TiledError> {
+    if all.is_empty() {
+        return Ok(Vec::new());
+    }
+    if width == 0 {
+        return Err(TiledError::Other("Tile data can't be split into rows of 0 tiles".to_string()));
+    }
     let mut data = Vec::new();
-    for chunk in all.chunks((width * 4) as usize) {
+    for chunk in all.chunks(width as usize * 4) {
+        if chunk.len() != width as usize * 4 {
+            return Err(TiledError::Other(format!("Tile data has {} bytes, which isn't a whole number of {} tile rows",
+                                                 all.len(), width)));
+        }
         let mut row = Vec::new();
         for i in 0 .. width {
             let start: usize = i as usize * 4;
@@ -882,16 +2921,16 @@ fn convert_to_u32(all: &Vec<u8>, width: u32) -> Vec<Vec<u32>> {
         }
         data.push(row);
     }
-    data
+    Ok(data)
 }
 
-fn parse_impl<R: Read, P: AsRef<Path>>(reader: R, map_path: Option<P>) -> Result<Map, TiledError> {
+fn parse_impl<R: Read, RR: ResourceReader>(reader: R, map_path: Option<&Path>, loader: &mut Loader<RR>) -> Result<Map, TiledError> {
     let mut parser = EventReader::new(reader);
     loop {
         match try!(parser.next().map_err(TiledError::XmlDecodingError)) {
             XmlEvent::StartElement {name, attributes, ..}  => {
                 if name.local_name == "map" {
-                    return Map::new(&mut parser, attributes, map_path);
+                    return Map::new(&mut parser, attributes, map_path, loader);
                 }
             }
             XmlEvent::EndDocument => return Err(TiledError::PrematureEnd("Document ended before map was parsed".to_string())),
@@ -905,20 +2944,97 @@ fn parse_impl<R: Read, P: AsRef<Path>>(reader: R, map_path: Option<P>) -> Result
 /// relative to the map file's path.
 pub fn parse_file<P: AsRef<Path>>(path: P) -> Result<Map, TiledError> {
     let file = File::open(path.as_ref()).map_err(|_| TiledError::Other(format!("Map file not found: {:?}", path.as_ref())))?;
-    parse_impl(file, Some(path))
+    parse_impl(file, Some(path.as_ref()), &mut Loader::new())
 }
 
 /// Parse a buffer hopefully containing the contents of a Tiled file and try to
 /// parse it.
 pub fn parse<R: Read>(reader: R) -> Result<Map, TiledError> {
-    parse_impl::<_, &Path>(reader, None)
+    parse_impl(reader, None, &mut Loader::new())
+}
+
+/// Parse a buffer hopefully containing the contents of a Tiled map, reading
+/// external tilesets and templates through `resources`. `path` is where the
+/// map itself lives and is only used to resolve relative references, the map
+/// is read from `reader`.
+///
+/// Use a `Loader` to share tilesets between maps.
+pub fn parse_with_reader<R: Read, P: AsRef<Path>, RR: ResourceReader>(reader: R, path: P, resources: &mut RR) -> Result<Map, TiledError> {
+    parse_impl(reader, Some(path.as_ref()), &mut Loader::with_reader(resources))
 }
 
 /// Parse a buffer hopefully containing the contents of a Tiled tileset.
 ///
 /// External tilesets do not have a firstgid attribute.  That lives in the
 /// map. You must pass in `first_gid`.  If you do not need to use gids for anything,
-/// passing in 1 will work fine.
-pub fn parse_tileset<R: Read>(reader: R, first_gid: u32) -> Result<Tileset, TiledError> {
-    Tileset::new_external(reader, first_gid)
+/// passing in 1 will work fine, or use `parse_external_tileset`.
+pub fn parse_tileset<R: Read>(reader: R, first_gid: u32) -> Result<MapTileset, TiledError> {
+    let tileset = Tileset::new_external(reader)?;
+    Ok(MapTileset {first_gid, tileset: Arc::new(tileset), source: None})
+}
+
+/// Parse a buffer hopefully containing the contents of a Tiled tileset, on
+/// its own rather than as used by a map.
+pub fn parse_external_tileset<R: Read>(reader: R) -> Result<Tileset, TiledError> {
+    Tileset::new_external(reader)
+}
+
+/// Parse a file hopefully containing a Tiled map in JSON format (usually with
+/// a `.tmj` or `.json` extension). External tilesets are loaded relative to
+/// the map file's path.
+pub fn parse_json_file<P: AsRef<Path>>(path: P) -> Result<Map, TiledError> {
+    let file = File::open(path.as_ref()).map_err(|_| TiledError::Other(format!("Map file not found: {:?}", path.as_ref())))?;
+    json::parse_map(file, Some(path.as_ref()), &mut Loader::new())
+}
+
+/// Parse a buffer hopefully containing the contents of a Tiled map in JSON
+/// format.
+pub fn parse_json<R: Read>(reader: R) -> Result<Map, TiledError> {
+    json::parse_map(reader, None, &mut Loader::new())
+}
+
+/// Parse a buffer hopefully containing the contents of a Tiled map in JSON
+/// format, reading external tilesets and templates through `resources`. See
+/// `parse_with_reader`.
+pub fn parse_json_with_reader<R: Read, P: AsRef<Path>, RR: ResourceReader>(reader: R, path: P, resources: &mut RR) -> Result<Map, TiledError> {
+    json::parse_map(reader, Some(path.as_ref()), &mut Loader::with_reader(resources))
+}
+
+/// Parse a buffer hopefully containing the contents of a Tiled tileset in JSON
+/// format (usually with a `.tsj` or `.json` extension).
+///
+pub fn parse_json_tileset<R: Read>(reader: R) -> Result<Tileset, TiledError> {
+    json::parse_tileset(reader)
+}
+
+/// Write a map in the TMX format. Tile layer data is written with the
+/// encoding and compression given in `options`.
+///
+/// Parsing the written file gives back an equal `Map`, except that objects
+/// created from a template are written with all their values and no longer
+/// refer to the template.
+///
+/// Tilesets loaded from a file are written as a reference to it, see
+/// `WriteOptions::external_tilesets` and `WriteOptions::map_path`.
+pub fn write_map<W: Write>(map: &Map, writer: W, options: &WriteOptions) -> Result<(), TiledError> {
+    writer::write_map(map, writer, options)
+}
+
+/// Write a tileset in the TSX format, as used for external tileset files.
+pub fn write_tileset<W: Write>(tileset: &Tileset, writer: W) -> Result<(), TiledError> {
+    writer::write_tileset(tileset, writer)
+}
+
+/// Write a tileset to a TSX file. See `write_tileset`.
+pub fn write_tileset_file<P: AsRef<Path>>(tileset: &Tileset, path: P) -> Result<(), TiledError> {
+    let file = File::create(path.as_ref()).map_err(|_| TiledError::Other(format!("Could not create tileset file: {:?}", path.as_ref())))?;
+    write_tileset(tileset, file)
+}
+
+/// Write a map to a TMX file. See `write_map`. The sources of external
+/// tilesets are written relative to `path`.
+pub fn write_map_file<P: AsRef<Path>>(map: &Map, path: P, options: &WriteOptions) -> Result<(), TiledError> {
+    let file = File::create(path.as_ref()).map_err(|_| TiledError::Other(format!("Could not create map file: {:?}", path.as_ref())))?;
+    let options = WriteOptions {map_path: Some(path.as_ref().to_path_buf()), ..options.clone()};
+    write_map(map, file, &options)
 }
@@ -0,0 +1,177 @@
+// Loading of many maps which share their external tilesets and templates.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use {FilesystemResourceReader, LayerTile, Map, MapTileset, PropertyTypes, ResourceReader, Template, TiledError, Tileset};
+use {json, parse_impl, resource};
+
+/// Loads maps and keeps the external tilesets and object templates they use,
+/// so that maps using the same tileset file share one parsed `Tileset`
+/// instead of each parsing their own copy.
+///
+/// Tilesets and templates are cached by their path, resolved relative to the map which
+/// referenced them. Files are read through a `ResourceReader`, which reads
+/// from the filesystem by default.
+///
+/// When given the `PropertyTypes` of a project, the loader fills in the
+/// missing members of class properties in everything it loads.
+#[derive(Debug)]
+pub struct Loader<RR: ResourceReader = FilesystemResourceReader> {
+    resources: RR,
+    tilesets: HashMap<PathBuf, Arc<Tileset>>,
+    templates: HashMap<PathBuf, Arc<Template>>,
+    property_types: PropertyTypes,
+}
+
+impl Loader<FilesystemResourceReader> {
+    pub fn new() -> Loader<FilesystemResourceReader> {
+        Loader::with_reader(FilesystemResourceReader)
+    }
+}
+
+impl Default for Loader<FilesystemResourceReader> {
+    fn default() -> Loader<FilesystemResourceReader> {
+        Loader::new()
+    }
+}
+
+impl<RR: ResourceReader> Loader<RR> {
+    pub fn with_reader(resources: RR) -> Loader<RR> {
+        Loader {resources, tilesets: HashMap::new(), templates: HashMap::new(), property_types: PropertyTypes::new()}
+    }
+
+    /// Loads a map in the TMX format. The map is read through the loader's
+    /// `ResourceReader` as well.
+    pub fn load_tmx_map<P: AsRef<Path>>(&mut self, path: P) -> Result<Map, TiledError> {
+        let path = path.as_ref();
+        let file = self.read(path, "Map")?;
+        let mut map = parse_impl(file, Some(path), self)?;
+        self.property_types.fill_map(&mut map);
+        Ok(map)
+    }
+
+    /// Loads a map in Tiled's JSON format.
+    pub fn load_json_map<P: AsRef<Path>>(&mut self, path: P) -> Result<Map, TiledError> {
+        let path = path.as_ref();
+        let file = self.read(path, "Map")?;
+        let mut map = json::parse_map(file, Some(path), self)?;
+        self.property_types.fill_map(&mut map);
+        Ok(map)
+    }
+
+    /// Loads a tileset file, or returns the cached tileset when it was
+    /// loaded before. Files ending in `.tsj` or `.json` are parsed as JSON,
+    /// anything else as TSX.
+    pub fn load_tileset<P: AsRef<Path>>(&mut self, path: P) -> Result<Arc<Tileset>, TiledError> {
+        self.tileset(resource::normalize(path.as_ref()))
+    }
+
+    /// The tilesets loaded so far, keyed by their resolved path.
+    pub fn tilesets(&self) -> &HashMap<PathBuf, Arc<Tileset>> {
+        &self.tilesets
+    }
+
+    /// Loads an object template, or returns the cached template when it was
+    /// loaded before. Files ending in `.tj` or `.json` are parsed as JSON,
+    /// anything else as TX.
+    pub fn load_template<P: AsRef<Path>>(&mut self, path: P) -> Result<Arc<Template>, TiledError> {
+        self.template(resource::normalize(path.as_ref()))
+    }
+
+    /// The object templates loaded so far, keyed by their resolved path.
+    pub fn templates(&self) -> &HashMap<PathBuf, Arc<Template>> {
+        &self.templates
+    }
+
+    /// Forgets all cached tilesets and templates. Maps loaded before keep
+    /// theirs.
+    pub fn clear(&mut self) {
+        self.tilesets.clear();
+        self.templates.clear();
+    }
+
+    /// Sets the classes used to fill in class properties. Cached tilesets
+    /// and templates are forgotten, so that they are filled when loaded
+    /// again.
+    pub fn set_property_types(&mut self, property_types: PropertyTypes) {
+        self.property_types = property_types;
+        self.clear();
+    }
+
+    pub fn property_types(&self) -> &PropertyTypes {
+        &self.property_types
+    }
+
+    pub fn reader(&self) -> &RR {
+        &self.resources
+    }
+
+    pub fn reader_mut(&mut self) -> &mut RR {
+        &mut self.resources
+    }
+
+    // Builds the entry for an external tileset referenced as `source` from
+    // the map at `map_path`.
+    pub(crate) fn map_tileset(&mut self, map_path: &Path, source: &str, first_gid: u32) -> Result<MapTileset, TiledError> {
+        let path = resource::resolve_path(map_path, source);
+        let tileset = self.tileset(path.clone())?;
+        Ok(MapTileset {first_gid, tileset, source: Some(path)})
+    }
+
+    // Resolves the template referenced as `source` by an object of the map at
+    // `map_path`. The template's tile is translated to the GIDs the map gives
+    // to the template's tileset.
+    pub(crate) fn object_template(&mut self, map_path: Option<&Path>, source: &str, tilesets: &[MapTileset])
+                                  -> Result<(Arc<Template>, Option<LayerTile>), TiledError> {
+        let map_path = map_path.ok_or_else(|| TiledError::Other("Maps with object templates must know their file location.  See parse_file(Path).".to_string()))?;
+        let template = self.template(resource::resolve_path(map_path, source))?;
+        let tile = match (template.object.tile, template.tileset.as_ref()) {
+            (Some(tile), Some(template_tileset)) => {
+                let map_tileset = tilesets.iter().find(|t| t.source.is_some() && t.source == template_tileset.source)
+                    .ok_or_else(|| TiledError::Other(format!("Template {:?} uses a tileset the map doesn't have", template.source)))?;
+                let id = tile.gid.checked_sub(template_tileset.first_gid)
+                    .ok_or_else(|| TiledError::Other(format!("Template {:?} has a tile outside its tileset", template.source)))?;
+                Some(LayerTile {gid: id + map_tileset.first_gid, ..tile})
+            }
+            (tile, _) => tile,
+        };
+        Ok((template, tile))
+    }
+
+    fn template(&mut self, path: PathBuf) -> Result<Arc<Template>, TiledError> {
+        if let Some(template) = self.templates.get(&path) {
+            return Ok(template.clone());
+        }
+        let file = self.read(&path, "Template")?;
+        let template = match path.extension().and_then(|e| e.to_str()) {
+            Some("tj") | Some("json") => json::parse_template(file, &path, self)?,
+            _ => Template::new(file, &path, self)?,
+        };
+        let template = Arc::new(template);
+        self.templates.insert(path, template.clone());
+        Ok(template)
+    }
+
+    // Both map formats can reference tilesets in either format, so the file
+    // extension decides how an external tileset is parsed.
+    fn tileset(&mut self, path: PathBuf) -> Result<Arc<Tileset>, TiledError> {
+        if let Some(tileset) = self.tilesets.get(&path) {
+            return Ok(tileset.clone());
+        }
+        let file = self.read(&path, "External tileset")?;
+        let mut tileset = match path.extension().and_then(|e| e.to_str()) {
+            Some("tsj") | Some("json") => json::parse_tileset(file)?,
+            _ => Tileset::new_external(file)?,
+        };
+        self.property_types.fill_tileset(&mut tileset);
+        let tileset = Arc::new(tileset);
+        self.tilesets.insert(path, tileset.clone());
+        Ok(tileset)
+    }
+
+    fn read(&mut self, path: &Path, what: &str) -> Result<RR::Resource, TiledError> {
+        self.resources.read_from(path).map_err(|_| TiledError::Other(format!("{} file not found: {:?}", what, path)))
+    }
+}
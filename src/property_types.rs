@@ -0,0 +1,186 @@
+// The custom classes of a Tiled project, used to fill in the members which
+// class properties leave at their defaults.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+
+use {Group, Map, Object, Properties, PropertyValue, TiledError, Tileset};
+use json;
+
+/// The classes declared in a Tiled project, with the default values of their
+/// members.
+///
+/// Tiled only saves the members of a class property which differ from their
+/// defaults, and the project keeps the defaults. Filling a map or tileset
+/// adds the missing members to every class property in it, including classes
+/// nested in other classes.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct PropertyTypes {
+    classes: HashMap<String, Properties>,
+}
+
+impl PropertyTypes {
+    pub fn new() -> PropertyTypes {
+        PropertyTypes::default()
+    }
+
+    /// Reads the `propertyTypes` of a `.tiled-project` file.
+    pub fn from_project<R: Read>(reader: R) -> Result<PropertyTypes, TiledError> {
+        Ok(PropertyTypes {classes: json::parse_property_types(reader)?})
+    }
+
+    pub fn from_project_file<P: AsRef<Path>>(path: P) -> Result<PropertyTypes, TiledError> {
+        let file = File::open(path.as_ref()).map_err(|_| TiledError::Other(format!("Project file not found: {:?}", path.as_ref())))?;
+        PropertyTypes::from_project(file)
+    }
+
+    /// Declares a class, or replaces the class with the same name.
+    pub fn add_class(&mut self, name: String, members: Properties) {
+        self.classes.insert(name, members);
+    }
+
+    /// The members of the class called `name` with their default values.
+    pub fn class(&self, name: &str) -> Option<&Properties> {
+        self.classes.get(name)
+    }
+
+    /// Adds the missing members of the class properties in `properties`.
+    ///
+    /// Members read from JSON have no type, so where the type guessed from
+    /// the value differs from the declared one, like an int stored for a
+    /// float member, the value is converted to the declared type.
+    pub fn fill_properties(&self, properties: &mut Properties) {
+        for value in properties.values_mut() {
+            self.fill_value(value, &mut Vec::new());
+        }
+    }
+
+    /// Fills the properties of the map, its layers, objects and embedded
+    /// tilesets. External tilesets are left alone, as they can be shared with
+    /// other maps. A `Loader` with these types fills them once when loading
+    /// them, or use `fill_tileset`.
+    pub fn fill_map(&self, map: &mut Map) {
+        if self.classes.is_empty() {
+            return;
+        }
+        self.fill_properties(&mut map.properties);
+        for map_tileset in map.tilesets.iter_mut().filter(|t| t.source.is_none()) {
+            if self.tileset_has_classes(&map_tileset.tileset) {
+                self.fill_tileset(Arc::make_mut(&mut map_tileset.tileset));
+            }
+        }
+        for layer in &mut map.layers {
+            self.fill_properties(&mut layer.properties);
+        }
+        for layer in &mut map.image_layers {
+            self.fill_properties(&mut layer.properties);
+        }
+        for object_group in &mut map.object_groups {
+            self.fill_objects(&mut object_group.objects);
+        }
+        for group in &mut map.groups {
+            self.fill_group(group);
+        }
+    }
+
+    /// Fills the properties of the tileset and of everything in it.
+    pub fn fill_tileset(&self, tileset: &mut Tileset) {
+        self.fill_properties(&mut tileset.properties);
+        for tile in &mut tileset.tiles {
+            self.fill_properties(&mut tile.properties);
+            if let Some(ref mut group) = tile.objectgroup {
+                self.fill_objects(&mut group.objects);
+            }
+        }
+        for terrain in &mut tileset.terrains {
+            self.fill_properties(&mut terrain.properties);
+        }
+        for wang_set in &mut tileset.wang_sets {
+            self.fill_properties(&mut wang_set.properties);
+            for colour in &mut wang_set.colours {
+                self.fill_properties(&mut colour.properties);
+            }
+        }
+    }
+
+    // Whether any property of the tileset is of a known class, so that
+    // filling the tileset has something to do.
+    fn tileset_has_classes(&self, tileset: &Tileset) -> bool {
+        let has_classes = |properties: &Properties| properties.values().any(|value| match *value {
+            PropertyValue::ClassValue {ref property_type, ..} => self.classes.contains_key(property_type),
+            _ => false,
+        });
+        has_classes(&tileset.properties)
+            || tileset.tiles.iter().any(|tile| {
+                has_classes(&tile.properties)
+                    || tile.objectgroup.iter().any(|group| group.objects.iter().any(|o| has_classes(&o.properties)))
+            })
+            || tileset.terrains.iter().any(|terrain| has_classes(&terrain.properties))
+            || tileset.wang_sets.iter().any(|wang_set| {
+                has_classes(&wang_set.properties) || wang_set.colours.iter().any(|colour| has_classes(&colour.properties))
+            })
+    }
+
+    fn fill_group(&self, group: &mut Group) {
+        self.fill_properties(&mut group.properties);
+        for layer in &mut group.layers {
+            self.fill_properties(&mut layer.properties);
+        }
+        for layer in &mut group.image_layers {
+            self.fill_properties(&mut layer.properties);
+        }
+        for object_group in &mut group.object_groups {
+            self.fill_objects(&mut object_group.objects);
+        }
+        for group in &mut group.groups {
+            self.fill_group(group);
+        }
+    }
+
+    fn fill_objects(&self, objects: &mut [Object]) {
+        for object in objects {
+            self.fill_properties(&mut object.properties);
+        }
+    }
+
+    // `filling` holds the classes being filled further up, so that a class
+    // containing itself doesn't recurse forever.
+    fn fill_value(&self, value: &mut PropertyValue, filling: &mut Vec<String>) {
+        if let PropertyValue::ClassValue {ref property_type, ref mut properties} = *value {
+            let members = match self.classes.get(property_type) {
+                Some(members) if !filling.contains(property_type) => members,
+                _ => return,
+            };
+            filling.push(property_type.clone());
+            for (name, default) in members {
+                let member = properties.entry(name.clone()).or_insert_with(|| default.clone());
+                convert(member, default);
+                self.fill_value(member, filling);
+            }
+            filling.pop();
+        }
+    }
+}
+
+// Converts a member read from JSON to the type of its declared default, where
+// the guessed type can be wrong.
+fn convert(value: &mut PropertyValue, declared: &PropertyValue) {
+    let converted = match (&*value, declared) {
+        (PropertyValue::IntValue(v), PropertyValue::FloatValue(_)) => PropertyValue::FloatValue(*v as f32),
+        (PropertyValue::StringValue(v), PropertyValue::ColorValue(_)) => {
+            match PropertyValue::new("color".to_string(), v.clone()) {
+                Ok(colour) => colour,
+                Err(_) => return,
+            }
+        }
+        (PropertyValue::ClassValue {property_type, properties}, PropertyValue::ClassValue {property_type: class, ..})
+            if property_type.is_empty() => {
+            PropertyValue::ClassValue {property_type: class.clone(), properties: properties.clone()}
+        }
+        _ => return,
+    };
+    *value = converted;
+}
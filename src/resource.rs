@@ -0,0 +1,119 @@
+// Reading of the files a map refers to: external tilesets, object templates
+// and images.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Cursor, Read};
+use std::path::{Component, Path, PathBuf};
+
+/// Something that can open the files a map or tileset refers to, like
+/// external tilesets, object templates and images.
+///
+/// The paths passed to `read_from` are built from the path of the referring
+/// file and the relative path stored in it, so they only need to make sense
+/// to the reader itself. A reader for a packed archive can use them as keys
+/// into the archive.
+pub trait ResourceReader {
+    type Resource: Read;
+
+    fn read_from(&mut self, path: &Path) -> Result<Self::Resource, io::Error>;
+}
+
+impl<T: ResourceReader> ResourceReader for &mut T {
+    type Resource = T::Resource;
+
+    fn read_from(&mut self, path: &Path) -> Result<T::Resource, io::Error> {
+        (**self).read_from(path)
+    }
+}
+
+/// Reads resources from the filesystem. This is what `parse_file` uses.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub struct FilesystemResourceReader;
+
+impl FilesystemResourceReader {
+    pub fn new() -> FilesystemResourceReader {
+        FilesystemResourceReader
+    }
+}
+
+impl ResourceReader for FilesystemResourceReader {
+    type Resource = File;
+
+    fn read_from(&mut self, path: &Path) -> Result<File, io::Error> {
+        File::open(path)
+    }
+}
+
+/// Reads resources from a set of files held in memory, keyed by their path.
+///
+/// Paths are compared after resolving `.` and `..` components, so
+/// `maps/../tilesheet.tsx` finds a file inserted as `tilesheet.tsx`.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct MemoryResourceReader {
+    files: HashMap<PathBuf, Vec<u8>>,
+}
+
+impl MemoryResourceReader {
+    pub fn new() -> MemoryResourceReader {
+        MemoryResourceReader::default()
+    }
+
+    /// Adds a file, replacing any file previously stored under the same path.
+    pub fn insert<P: AsRef<Path>, D: Into<Vec<u8>>>(&mut self, path: P, data: D) {
+        self.files.insert(normalize(path.as_ref()), data.into());
+    }
+}
+
+impl ResourceReader for MemoryResourceReader {
+    type Resource = Cursor<Vec<u8>>;
+
+    fn read_from(&mut self, path: &Path) -> Result<Cursor<Vec<u8>>, io::Error> {
+        match self.files.get(&normalize(path)) {
+            Some(data) => Ok(Cursor::new(data.clone())),
+            None => Err(io::Error::new(io::ErrorKind::NotFound, format!("{:?} not found", path))),
+        }
+    }
+}
+
+/// The path of a file referenced as `source` from the file at `base`.
+pub fn resolve_path(base: &Path, source: &str) -> PathBuf {
+    normalize(&base.with_file_name(source))
+}
+
+/// Resolves `.` and `..` components without touching the filesystem. Leading
+/// `..` components of relative paths are kept.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut components: Vec<Component> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match components.last() {
+                Some(&Component::Normal(_)) => { components.pop(); }
+                Some(&Component::RootDir) | Some(&Component::Prefix(_)) => {}
+                _ => components.push(component),
+            },
+            _ => components.push(component),
+        }
+    }
+    components.iter().collect()
+}
+
+/// `path` written relative to the directory `dir`, falling back to `path`
+/// itself when there is no relative path between them.
+pub fn relative_path(path: &Path, dir: &Path) -> PathBuf {
+    let path = normalize(path);
+    let dir = normalize(dir);
+    let common = path.components().zip(dir.components()).take_while(|&(a, b)| a == b).count();
+    let mut relative = PathBuf::new();
+    for component in dir.components().skip(common) {
+        match component {
+            Component::Normal(_) => relative.push(".."),
+            _ => return path,
+        }
+    }
+    for component in path.components().skip(common) {
+        relative.push(component.as_os_str());
+    }
+    relative
+}
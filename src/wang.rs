@@ -0,0 +1,237 @@
+// Painting Wang colours onto tile layers at runtime, picking tiles the way
+// Tiled's terrain brush does.
+
+use std::collections::{HashMap, VecDeque};
+
+use {Layer, LayerTile, MapTileset, WangId, WangSet, WangSetType};
+
+// A neighbour's offset from a cell, and for each position of the cell's Wang
+// id the neighbour shares, the position in the cell's and the neighbour's id.
+type Neighbour = ((i32, i32), &'static [(usize, usize)]);
+
+const NEIGHBOURS: [Neighbour; 8] = [
+    ((0, -1), &[(WangId::TOP, WangId::BOTTOM), (WangId::TOP_LEFT, WangId::BOTTOM_LEFT),
+                (WangId::TOP_RIGHT, WangId::BOTTOM_RIGHT)]),
+    ((1, -1), &[(WangId::TOP_RIGHT, WangId::BOTTOM_LEFT)]),
+    ((1, 0), &[(WangId::RIGHT, WangId::LEFT), (WangId::TOP_RIGHT, WangId::TOP_LEFT),
+               (WangId::BOTTOM_RIGHT, WangId::BOTTOM_LEFT)]),
+    ((1, 1), &[(WangId::BOTTOM_RIGHT, WangId::TOP_LEFT)]),
+    ((0, 1), &[(WangId::BOTTOM, WangId::TOP), (WangId::BOTTOM_LEFT, WangId::TOP_LEFT),
+               (WangId::BOTTOM_RIGHT, WangId::TOP_RIGHT)]),
+    ((-1, 1), &[(WangId::BOTTOM_LEFT, WangId::TOP_RIGHT)]),
+    ((-1, 0), &[(WangId::LEFT, WangId::RIGHT), (WangId::TOP_LEFT, WangId::TOP_RIGHT),
+                (WangId::BOTTOM_LEFT, WangId::BOTTOM_RIGHT)]),
+    ((-1, -1), &[(WangId::TOP_LEFT, WangId::BOTTOM_RIGHT)]),
+];
+
+/// A small seedable random number generator (SplitMix64), used to choose
+/// between equally good tiles. Painting with the same seed always picks the
+/// same tiles.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct WangRng {
+    state: u64,
+}
+
+impl WangRng {
+    pub fn new(seed: u64) -> WangRng {
+        WangRng {state: seed}
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `[0, 1)`.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+/// Paints the colours of a Wang set onto a tile layer and replaces the
+/// painted cell and the cells around it with matching tiles of the set.
+///
+/// Only cells holding a tile of the Wang set are updated around the painted
+/// cell. Empty cells and tiles of other sets are left alone, and the parts
+/// of the painted cell they share are free to take any colour.
+#[derive(Debug, Clone)]
+pub struct Autotiler<'a> {
+    tileset: &'a MapTileset,
+    wang_set: &'a WangSet,
+    rng: WangRng,
+}
+
+impl<'a> Autotiler<'a> {
+    /// `wang_set` must be one of the Wang sets of `tileset`, which is the
+    /// tileset as used by the map that the layers belong to.
+    pub fn new(tileset: &'a MapTileset, wang_set: &'a WangSet, seed: u64) -> Autotiler<'a> {
+        Autotiler {tileset, wang_set, rng: WangRng::new(seed)}
+    }
+
+    pub fn wang_set(&self) -> &'a WangSet {
+        self.wang_set
+    }
+
+    /// The Wang id of a tile as placed in a layer, with its flips applied, or
+    /// `None` if the tile isn't part of the Wang set.
+    pub fn wang_id(&self, tile: LayerTile) -> Option<WangId> {
+        if tile.is_empty() || tile.gid < self.tileset.first_gid {
+            return None;
+        }
+        let id = tile.gid - self.tileset.first_gid;
+        let wang_id = self.wang_set.wang_tiles.iter().find(|t| t.tile_id == id)?.wang_id;
+        Some(flip_wang_id(wang_id, tile.flip_h, tile.flip_v, tile.flip_d))
+    }
+
+    /// Paints `colour`, an index into `WangSet::colours` plus one, onto the
+    /// cell at `x`, `y`. Depending on the type of the Wang set this colours
+    /// the cell's corners, edges, or both. Returns the coordinates of the
+    /// cells whose tile changed, or nothing if `colour` isn't one of the set.
+    ///
+    /// When a neighbour has no tile matching the colours around it, the
+    /// closest tile can differ from its own neighbours in turn, so changes
+    /// spread outwards until the tiles fit. Each cell changes at most once,
+    /// and cells that already changed keep their tile.
+    pub fn paint(&mut self, layer: &mut Layer, x: i32, y: i32, colour: u8) -> Vec<(i32, i32)> {
+        if colour == 0 || colour as usize > self.wang_set.colours.len() {
+            return Vec::new();
+        }
+        let painted = self.painted_wang_id(colour);
+        let mut changed = Vec::new();
+        if self.place(layer, x, y, painted) {
+            changed.push((x, y));
+        }
+        // The cells whose tiles are settled, with the colours their
+        // neighbours have to match.
+        let mut settled = HashMap::new();
+        settled.insert((x, y), painted);
+        let mut queue = VecDeque::new();
+        queue.push_back((x, y));
+        while let Some((cx, cy)) = queue.pop_front() {
+            for &((dx, dy), _) in NEIGHBOURS.iter() {
+                let (nx, ny) = (cx + dx, cy + dy);
+                if settled.contains_key(&(nx, ny)) {
+                    continue;
+                }
+                let current = match layer.get_tile(nx, ny).and_then(|tile| self.wang_id(tile)) {
+                    Some(current) => current,
+                    None => continue,
+                };
+                let wanted = wanted_wang_id(current, nx, ny, &settled);
+                if wanted != current && self.place(layer, nx, ny, wanted) {
+                    changed.push((nx, ny));
+                    let placed = layer.get_tile(nx, ny).and_then(|tile| self.wang_id(tile)).unwrap_or(wanted);
+                    settled.insert((nx, ny), placed);
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+        changed
+    }
+
+    // The Wang id of a cell painted all over with `colour`.
+    fn painted_wang_id(&self, colour: u8) -> WangId {
+        let mut wang_id = WangId::default();
+        for (index, c) in wang_id.0.iter_mut().enumerate() {
+            let is_corner = index % 2 == 1;
+            *c = match self.wang_set.set_type {
+                WangSetType::Corner if is_corner => colour,
+                WangSetType::Edge if !is_corner => colour,
+                WangSetType::Mixed => colour,
+                _ => 0,
+            };
+        }
+        wang_id
+    }
+
+    // Puts the tile that best matches `wanted` at `x`, `y`. Returns whether
+    // the tile there changed.
+    fn place(&mut self, layer: &mut Layer, x: i32, y: i32, wanted: WangId) -> bool {
+        let tile_id = match self.pick(wanted) {
+            Some(tile_id) => tile_id,
+            None => return false,
+        };
+        let tile = LayerTile::new(self.tileset.first_gid + tile_id);
+        if layer.get_tile(x, y) == Some(tile) {
+            return false;
+        }
+        layer.set_tile(x, y, tile)
+    }
+
+    // Finds the tiles differing from `wanted` in the fewest places, ignoring
+    // places where `wanted` has no colour, and picks one of them at random,
+    // weighted by the probability of the tile and of its colours.
+    fn pick(&mut self, wanted: WangId) -> Option<u32> {
+        let mismatches = |wang_id: &WangId| {
+            wanted.0.iter().zip(wang_id.0.iter()).filter(|&(&w, &c)| w != 0 && w != c).count()
+        };
+        let best = self.wang_set.wang_tiles.iter().map(|t| mismatches(&t.wang_id)).min()?;
+        let candidates: Vec<(u32, f32)> = self.wang_set.wang_tiles.iter()
+            .filter(|t| mismatches(&t.wang_id) == best)
+            .map(|t| (t.tile_id, self.probability(t.tile_id, &t.wang_id)))
+            .collect();
+        let total: f32 = candidates.iter().map(|&(_, p)| p).sum();
+        if total <= 0.0 {
+            let index = (self.rng.next_u64() % candidates.len() as u64) as usize;
+            return Some(candidates[index].0);
+        }
+        let mut target = self.rng.next_f32() * total;
+        for &(tile_id, probability) in &candidates {
+            if target < probability {
+                return Some(tile_id);
+            }
+            target -= probability;
+        }
+        candidates.last().map(|&(tile_id, _)| tile_id)
+    }
+
+    fn probability(&self, tile_id: u32, wang_id: &WangId) -> f32 {
+        let tile = self.tileset.get_tile(tile_id).map_or(1.0, |tile| tile.probability);
+        tile * wang_id.0.iter()
+            .filter(|&&c| c != 0)
+            .map(|&c| self.wang_set.colours.get(c as usize - 1).map_or(1.0, |colour| colour.probability))
+            .product::<f32>()
+    }
+}
+
+// The Wang id of the cell at `x`, `y` with the places it shares with settled
+// cells changed to their colours.
+fn wanted_wang_id(current: WangId, x: i32, y: i32, settled: &HashMap<(i32, i32), WangId>) -> WangId {
+    let mut wanted = current;
+    for &((dx, dy), shared) in NEIGHBOURS.iter() {
+        if let Some(neighbour) = settled.get(&(x + dx, y + dy)) {
+            for &(own, theirs) in shared {
+                if neighbour.0[theirs] != 0 {
+                    wanted.0[own] = neighbour.0[theirs];
+                }
+            }
+        }
+    }
+    wanted
+}
+
+// The Wang id of a tile drawn with the given flips. Like when drawing, the
+// diagonal flip is applied first.
+fn flip_wang_id(wang_id: WangId, flip_h: bool, flip_v: bool, flip_d: bool) -> WangId {
+    let remap = |wang_id: WangId, mapping: [usize; 8]| {
+        let mut flipped = WangId::default();
+        for (index, &from) in mapping.iter().enumerate() {
+            flipped.0[index] = wang_id.0[from];
+        }
+        flipped
+    };
+    let mut wang_id = wang_id;
+    if flip_d {
+        wang_id = remap(wang_id, [6, 5, 4, 3, 2, 1, 0, 7]);
+    }
+    if flip_h {
+        wang_id = remap(wang_id, [0, 7, 6, 5, 4, 3, 2, 1]);
+    }
+    if flip_v {
+        wang_id = remap(wang_id, [4, 3, 2, 1, 0, 7, 6, 5]);
+    }
+    wang_id
+}
@@ -0,0 +1,682 @@
+// Serialization of maps and tilesets back into the TMX and TSX formats.
+
+use std::collections::HashMap;
+use std::env;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use base64::u8en as encode_base64;
+use flate2::Compression;
+use flate2::write::{GzEncoder, ZlibEncoder};
+use xml::writer::{EmitterConfig, EventWriter, XmlEvent};
+
+use {Chunk, Colour, FillMode, Frame, Group, HorizontalAlignment, Image, ImageLayer, Layer, LayerRef, LayerTile, Map, Object,
+     ObjectAlignment, ObjectGroup, ObjectShape, Properties, PropertyValue, Tile, TiledError, TileRenderSize, Tileset,
+     VerticalAlignment, WangSet};
+use resource::relative_path;
+
+/// How the tiles of a layer are stored inside its `<data>` element.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum DataEncoding {
+    /// One `<tile gid="..."/>` element per tile.
+    Xml,
+    Csv,
+    Base64,
+}
+
+/// The compression applied to base64 encoded layer data.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum DataCompression {
+    Zlib,
+    Gzip,
+    /// Requires the `zstd` feature.
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+/// Options for `write_map` and `write_map_file`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct WriteOptions {
+    pub encoding: DataEncoding,
+    /// Only used with `DataEncoding::Base64`.
+    pub compression: Option<DataCompression>,
+    /// Tilesets with a `MapTileset::source` are written as a reference to
+    /// their file. This overrides the written path, keyed by the tileset's
+    /// `first_gid`, and also makes embedded tilesets references. The value
+    /// is the path of the tileset file relative to the map.
+    pub external_tilesets: HashMap<u32, String>,
+    /// Where the map is written to, so that the sources of external tilesets
+    /// can be written relative to it. `write_map_file` fills this in. Without
+    /// it, sources are written as they are in `MapTileset::source`.
+    pub map_path: Option<PathBuf>,
+}
+
+impl Default for WriteOptions {
+    fn default() -> WriteOptions {
+        WriteOptions {
+            encoding: DataEncoding::Csv,
+            compression: None,
+            external_tilesets: HashMap::new(),
+            map_path: None,
+        }
+    }
+}
+
+struct TmxWriter<'a, W: Write> {
+    writer: EventWriter<W>,
+    options: &'a WriteOptions,
+}
+
+impl<'a, W: Write> TmxWriter<'a, W> {
+    fn new(sink: W, options: &'a WriteOptions) -> TmxWriter<'a, W> {
+        let config = EmitterConfig {
+            perform_indent: true,
+            indent_string: " ".into(),
+            ..EmitterConfig::new()
+        };
+        TmxWriter {writer: EventWriter::new_with_config(sink, config), options}
+    }
+
+    fn start_document(&mut self) -> Result<(), TiledError> {
+        self.writer.write(XmlEvent::StartDocument {version: ::xml::common::XmlVersion::Version10, encoding: Some("UTF-8"), standalone: None})
+            .map_err(TiledError::XmlEncodingError)
+    }
+
+    fn start(&mut self, name: &str, attrs: &[(&str, String)]) -> Result<(), TiledError> {
+        let mut event = XmlEvent::start_element(name);
+        for &(key, ref value) in attrs {
+            event = event.attr(key, value);
+        }
+        self.writer.write(event).map_err(TiledError::XmlEncodingError)
+    }
+
+    fn end(&mut self) -> Result<(), TiledError> {
+        self.writer.write(XmlEvent::end_element()).map_err(TiledError::XmlEncodingError)
+    }
+
+    fn element(&mut self, name: &str, attrs: &[(&str, String)]) -> Result<(), TiledError> {
+        self.start(name, attrs)?;
+        self.end()
+    }
+
+    fn characters(&mut self, text: &str) -> Result<(), TiledError> {
+        self.writer.write(XmlEvent::characters(text)).map_err(TiledError::XmlEncodingError)
+    }
+
+    fn write_map(&mut self, map: &Map) -> Result<(), TiledError> {
+        self.start_document()?;
+        let mut attrs = vec![("version", map.version.clone())];
+        if let Some(ref tiled_version) = map.tiled_version {
+            attrs.push(("tiledversion", tiled_version.clone()));
+        }
+        attrs.push(("orientation", map.orientation.to_string()));
+        attrs.push(("renderorder", map.render_order.to_string()));
+        if map.compression_level != -1 {
+            attrs.push(("compressionlevel", map.compression_level.to_string()));
+        }
+        attrs.push(("width", map.width.to_string()));
+        attrs.push(("height", map.height.to_string()));
+        attrs.push(("tilewidth", map.tile_width.to_string()));
+        attrs.push(("tileheight", map.tile_height.to_string()));
+        if let Some(hex_side_length) = map.hex_side_length {
+            attrs.push(("hexsidelength", hex_side_length.to_string()));
+        }
+        if let Some(stagger_axis) = map.stagger_axis {
+            attrs.push(("staggeraxis", stagger_axis.to_string()));
+        }
+        if let Some(stagger_index) = map.stagger_index {
+            attrs.push(("staggerindex", stagger_index.to_string()));
+        }
+        if map.parallax_origin_x != 0.0 {
+            attrs.push(("parallaxoriginx", map.parallax_origin_x.to_string()));
+        }
+        if map.parallax_origin_y != 0.0 {
+            attrs.push(("parallaxoriginy", map.parallax_origin_y.to_string()));
+        }
+        if let Some(colour) = map.background_colour {
+            attrs.push(("backgroundcolor", colour.to_string()));
+        }
+        if map.infinite {
+            attrs.push(("infinite", "1".to_string()));
+        }
+        if let Some(next_layer_id) = map.next_layer_id {
+            attrs.push(("nextlayerid", next_layer_id.to_string()));
+        }
+        if let Some(next_object_id) = map.next_object_id {
+            attrs.push(("nextobjectid", next_object_id.to_string()));
+        }
+        self.start("map", &attrs)?;
+        self.write_properties(&map.properties)?;
+        for tileset in &map.tilesets {
+            let source = match (self.options.external_tilesets.get(&tileset.first_gid), tileset.source.as_ref()) {
+                (Some(source), _) => Some(source.clone()),
+                (None, Some(path)) => Some(self.tileset_source(path)),
+                (None, None) => None,
+            };
+            match source {
+                Some(source) => self.element("tileset", &[("firstgid", tileset.first_gid.to_string()),
+                                                          ("source", source)])?,
+                None => self.write_tileset(tileset, Some(tileset.first_gid))?,
+            }
+        }
+        for layer in map.ordered_layers() {
+            self.write_layer(layer)?;
+        }
+        self.end()
+    }
+
+    fn write_properties(&mut self, properties: &Properties) -> Result<(), TiledError> {
+        if properties.is_empty() {
+            return Ok(());
+        }
+        // Sorted so that writing the same map twice gives the same file.
+        let mut names: Vec<&String> = properties.keys().collect();
+        names.sort();
+        self.start("properties", &[])?;
+        for name in names {
+            let (property_type, value) = match properties[name] {
+                PropertyValue::BoolValue(v) => (Some("bool"), v.to_string()),
+                PropertyValue::FloatValue(v) => (Some("float"), v.to_string()),
+                PropertyValue::IntValue(v) => (Some("int"), v.to_string()),
+                PropertyValue::ColorValue(v) => (Some("color"), format!("#{:08x}", v)),
+                PropertyValue::StringValue(ref v) => (None, v.clone()),
+                PropertyValue::ClassValue {ref property_type, properties: ref members} => {
+                    let mut attrs = vec![("name", name.clone()), ("type", "class".to_string())];
+                    if !property_type.is_empty() {
+                        attrs.push(("propertytype", property_type.clone()));
+                    }
+                    self.start("property", &attrs)?;
+                    self.write_properties(members)?;
+                    self.end()?;
+                    continue;
+                }
+            };
+            let mut attrs = vec![("name", name.clone())];
+            if let Some(property_type) = property_type {
+                attrs.push(("type", property_type.to_string()));
+            }
+            attrs.push(("value", value));
+            self.element("property", &attrs)?;
+        }
+        self.end()
+    }
+
+    /// Writes the `<tileset>` element. Tilesets embedded in a map carry their
+    /// `firstgid`, tileset files don't.
+    fn write_tileset(&mut self, tileset: &Tileset, first_gid: Option<u32>) -> Result<(), TiledError> {
+        let mut attrs = Vec::new();
+        if let Some(first_gid) = first_gid {
+            attrs.push(("firstgid", first_gid.to_string()));
+        }
+        attrs.push(("name", tileset.name.clone()));
+        attrs.push(("tilewidth", tileset.tile_width.to_string()));
+        attrs.push(("tileheight", tileset.tile_height.to_string()));
+        if tileset.spacing != 0 {
+            attrs.push(("spacing", tileset.spacing.to_string()));
+        }
+        if tileset.margin != 0 {
+            attrs.push(("margin", tileset.margin.to_string()));
+        }
+        if let Some(tile_count) = tileset.tile_count {
+            attrs.push(("tilecount", tile_count.to_string()));
+        }
+        if let Some(columns) = tileset.columns {
+            attrs.push(("columns", columns.to_string()));
+        }
+        if tileset.object_alignment != ObjectAlignment::Unspecified {
+            attrs.push(("objectalignment", tileset.object_alignment.to_string()));
+        }
+        if tileset.tile_render_size != TileRenderSize::Tile {
+            attrs.push(("tilerendersize", tileset.tile_render_size.to_string()));
+        }
+        if tileset.fill_mode != FillMode::Stretch {
+            attrs.push(("fillmode", tileset.fill_mode.to_string()));
+        }
+        self.start("tileset", &attrs)?;
+        if tileset.tile_offset_x != 0 || tileset.tile_offset_y != 0 {
+            self.element("tileoffset", &[("x", tileset.tile_offset_x.to_string()),
+                                         ("y", tileset.tile_offset_y.to_string())])?;
+        }
+        if let Some(grid) = tileset.grid {
+            self.element("grid", &[("orientation", grid.orientation.to_string()),
+                                   ("width", grid.width.to_string()),
+                                   ("height", grid.height.to_string())])?;
+        }
+        self.write_properties(&tileset.properties)?;
+        for image in &tileset.images {
+            self.write_image(image)?;
+        }
+        if !tileset.terrains.is_empty() {
+            self.start("terraintypes", &[])?;
+            for terrain in &tileset.terrains {
+                self.start("terrain", &[("name", terrain.name.clone()),
+                                        ("tile", tile_or_none(terrain.tile))])?;
+                self.write_properties(&terrain.properties)?;
+                self.end()?;
+            }
+            self.end()?;
+        }
+        for tile in &tileset.tiles {
+            self.write_tile(tile)?;
+        }
+        if !tileset.wang_sets.is_empty() {
+            self.start("wangsets", &[])?;
+            for wang_set in &tileset.wang_sets {
+                self.write_wang_set(wang_set)?;
+            }
+            self.end()?;
+        }
+        self.end()
+    }
+
+    fn write_wang_set(&mut self, wang_set: &WangSet) -> Result<(), TiledError> {
+        self.start("wangset", &[("name", wang_set.name.clone()),
+                                ("type", wang_set.set_type.to_string()),
+                                ("tile", tile_or_none(wang_set.tile))])?;
+        self.write_properties(&wang_set.properties)?;
+        for colour in &wang_set.colours {
+            self.start("wangcolor", &[("name", colour.name.clone()),
+                                      ("color", colour.colour.to_string()),
+                                      ("tile", tile_or_none(colour.tile)),
+                                      ("probability", colour.probability.to_string())])?;
+            self.write_properties(&colour.properties)?;
+            self.end()?;
+        }
+        for tile in &wang_set.wang_tiles {
+            self.element("wangtile", &[("tileid", tile.tile_id.to_string()),
+                                       ("wangid", tile.wang_id.to_string())])?;
+        }
+        self.end()
+    }
+
+    fn write_tileset_document(&mut self, tileset: &Tileset) -> Result<(), TiledError> {
+        self.start_document()?;
+        self.write_tileset(tileset, None)
+    }
+
+    fn write_tile(&mut self, tile: &Tile) -> Result<(), TiledError> {
+        let mut attrs = vec![("id", tile.id.to_string())];
+        if !tile.tile_type.is_empty() {
+            attrs.push(("type", tile.tile_type.clone()));
+        }
+        if let Some(terrain) = tile.terrain {
+            let corners: Vec<String> = terrain.iter().map(|c| c.map_or(String::new(), |c| c.to_string())).collect();
+            attrs.push(("terrain", corners.join(",")));
+        }
+        if tile.probability != 1.0 {
+            attrs.push(("probability", tile.probability.to_string()));
+        }
+        if tile.x != 0 {
+            attrs.push(("x", tile.x.to_string()));
+        }
+        if tile.y != 0 {
+            attrs.push(("y", tile.y.to_string()));
+        }
+        if let Some(width) = tile.width {
+            attrs.push(("width", width.to_string()));
+        }
+        if let Some(height) = tile.height {
+            attrs.push(("height", height.to_string()));
+        }
+        self.start("tile", &attrs)?;
+        self.write_properties(&tile.properties)?;
+        for image in &tile.images {
+            self.write_image(image)?;
+        }
+        if let Some(ref group) = tile.objectgroup {
+            self.write_object_group(group)?;
+        }
+        if let Some(ref frames) = tile.animation {
+            self.write_animation(frames)?;
+        }
+        self.end()
+    }
+
+    fn write_animation(&mut self, frames: &[Frame]) -> Result<(), TiledError> {
+        self.start("animation", &[])?;
+        for frame in frames {
+            self.element("frame", &[("tileid", frame.tile_id.to_string()),
+                                    ("duration", frame.duration.to_string())])?;
+        }
+        self.end()
+    }
+
+    fn write_image(&mut self, image: &Image) -> Result<(), TiledError> {
+        let mut attrs = Vec::new();
+        if let Some(ref format) = image.format {
+            attrs.push(("format", format.clone()));
+        }
+        if !image.source.is_empty() {
+            attrs.push(("source", image.source.clone()));
+        }
+        if let Some(width) = image.width {
+            attrs.push(("width", width.to_string()));
+        }
+        if let Some(height) = image.height {
+            attrs.push(("height", height.to_string()));
+        }
+        if let Some(colour) = image.transparent_colour {
+            // Tiled writes the transparent colour without a leading '#'.
+            attrs.push(("trans", colour.to_string()[1..].to_string()));
+        }
+        match image.data {
+            Some(ref data) => {
+                self.start("image", &attrs)?;
+                self.start("data", &[("encoding", "base64".to_string())])?;
+                let encoded = encode_base64(data).map_err(|e| TiledError::Other(e.to_string()))?;
+                self.characters(&String::from_utf8_lossy(&encoded))?;
+                self.end()?;
+                self.end()
+            }
+            None => self.element("image", &attrs),
+        }
+    }
+
+    // The path of a tileset file relative to the written map, with `/` as
+    // separator like Tiled writes them.
+    fn tileset_source(&self, path: &Path) -> String {
+        let relative = match self.options.map_path {
+            Some(ref map_path) => {
+                let dir = map_path.parent().unwrap_or_else(|| Path::new(""));
+                // Relative paths are relative to the working directory, so
+                // both sides need to be absolute or relative to compare them.
+                let absolute = |p: &Path| env::current_dir().map(|cwd| cwd.join(p)).unwrap_or_else(|_| p.to_path_buf());
+                if path.is_absolute() == dir.is_absolute() {
+                    relative_path(path, dir)
+                } else {
+                    relative_path(&absolute(path), &absolute(dir))
+                }
+            }
+            None => path.to_path_buf(),
+        };
+        if relative.is_absolute() {
+            return relative.to_string_lossy().into_owned();
+        }
+        let parts: Vec<String> = relative.components().map(|c| c.as_os_str().to_string_lossy().into_owned()).collect();
+        parts.join("/")
+    }
+
+    fn write_layer(&mut self, layer: LayerRef) -> Result<(), TiledError> {
+        match layer {
+            LayerRef::Tile(layer) => self.write_tile_layer(layer),
+            LayerRef::Image(layer) => self.write_image_layer(layer),
+            LayerRef::Object(group) => self.write_object_group(group),
+            LayerRef::Group(group) => self.write_group(group),
+        }
+    }
+
+    fn write_tile_layer(&mut self, layer: &Layer) -> Result<(), TiledError> {
+        let (_, _, width, height) = layer.bounds();
+        let mut attrs = Vec::new();
+        push_id(&mut attrs, layer.id);
+        attrs.push(("name", layer.name.clone()));
+        attrs.push(("width", width.to_string()));
+        attrs.push(("height", height.to_string()));
+        push_common(&mut attrs, LayerRef::Tile(layer));
+        self.start("layer", &attrs)?;
+        self.write_properties(&layer.properties)?;
+
+        let mut attrs = Vec::new();
+        match self.options.encoding {
+            DataEncoding::Xml => {}
+            DataEncoding::Csv => attrs.push(("encoding", "csv".to_string())),
+            DataEncoding::Base64 => {
+                attrs.push(("encoding", "base64".to_string()));
+                match self.options.compression {
+                    Some(DataCompression::Zlib) => attrs.push(("compression", "zlib".to_string())),
+                    Some(DataCompression::Gzip) => attrs.push(("compression", "gzip".to_string())),
+                    #[cfg(feature = "zstd")]
+                    Some(DataCompression::Zstd) => attrs.push(("compression", "zstd".to_string())),
+                    None => {}
+                }
+            }
+        }
+        self.start("data", &attrs)?;
+        if layer.chunks.is_empty() {
+            self.write_tiles(&layer.tiles)?;
+        } else {
+            for chunk in &layer.chunks {
+                self.write_chunk(chunk)?;
+            }
+        }
+        self.end()?;
+        self.end()
+    }
+
+    fn write_chunk(&mut self, chunk: &Chunk) -> Result<(), TiledError> {
+        self.start("chunk", &[("x", chunk.x.to_string()),
+                              ("y", chunk.y.to_string()),
+                              ("width", chunk.width.to_string()),
+                              ("height", chunk.height.to_string())])?;
+        self.write_tiles(&chunk.tiles)?;
+        self.end()
+    }
+
+    fn write_tiles(&mut self, tiles: &[Vec<LayerTile>]) -> Result<(), TiledError> {
+        match self.options.encoding {
+            DataEncoding::Xml => {
+                for tile in tiles.iter().flat_map(|row| row.iter()) {
+                    if tile.is_empty() {
+                        self.element("tile", &[])?;
+                    } else {
+                        self.element("tile", &[("gid", tile.raw_gid().to_string())])?;
+                    }
+                }
+                Ok(())
+            }
+            DataEncoding::Csv => {
+                let rows: Vec<String> = tiles.iter().map(|row| {
+                    row.iter().map(|tile| tile.raw_gid().to_string()).collect::<Vec<_>>().join(",")
+                }).collect();
+                self.characters(&format!("\n{}\n", rows.join(",\n")))
+            }
+            DataEncoding::Base64 => {
+                let mut bytes = Vec::new();
+                for tile in tiles.iter().flat_map(|row| row.iter()) {
+                    let gid = tile.raw_gid();
+                    bytes.extend_from_slice(&[gid as u8, (gid >> 8) as u8, (gid >> 16) as u8, (gid >> 24) as u8]);
+                }
+                let bytes = match self.options.compression {
+                    None => bytes,
+                    Some(compression) => compress(&bytes, compression)?,
+                };
+                let encoded = encode_base64(&bytes).map_err(|e| TiledError::Other(e.to_string()))?;
+                self.characters(&format!("\n{}\n", String::from_utf8_lossy(&encoded)))
+            }
+        }
+    }
+
+    fn write_image_layer(&mut self, layer: &ImageLayer) -> Result<(), TiledError> {
+        let mut attrs = Vec::new();
+        push_id(&mut attrs, layer.id);
+        attrs.push(("name", layer.name.clone()));
+        push_common(&mut attrs, LayerRef::Image(layer));
+        if layer.repeat_x {
+            attrs.push(("repeatx", "1".to_string()));
+        }
+        if layer.repeat_y {
+            attrs.push(("repeaty", "1".to_string()));
+        }
+        self.start("imagelayer", &attrs)?;
+        self.write_properties(&layer.properties)?;
+        if let Some(ref image) = layer.image {
+            self.write_image(image)?;
+        }
+        self.end()
+    }
+
+    fn write_group(&mut self, group: &Group) -> Result<(), TiledError> {
+        let mut attrs = Vec::new();
+        push_id(&mut attrs, group.id);
+        attrs.push(("name", group.name.clone()));
+        push_common(&mut attrs, LayerRef::Group(group));
+        self.start("group", &attrs)?;
+        self.write_properties(&group.properties)?;
+        for layer in group.ordered_layers() {
+            self.write_layer(layer)?;
+        }
+        self.end()
+    }
+
+    fn write_object_group(&mut self, group: &ObjectGroup) -> Result<(), TiledError> {
+        let mut attrs = Vec::new();
+        push_id(&mut attrs, group.id);
+        if !group.name.is_empty() {
+            attrs.push(("name", group.name.clone()));
+        }
+        if let Some(colour) = group.colour {
+            attrs.push(("color", colour.to_string()));
+        }
+        push_common(&mut attrs, LayerRef::Object(group));
+        self.start("objectgroup", &attrs)?;
+        for object in &group.objects {
+            self.write_object(object)?;
+        }
+        self.end()
+    }
+
+    fn write_object(&mut self, object: &Object) -> Result<(), TiledError> {
+        let mut attrs = vec![("id", object.id.to_string())];
+        if let Some(tile) = object.tile {
+            attrs.push(("gid", tile.raw_gid().to_string()));
+        }
+        if !object.name.is_empty() {
+            attrs.push(("name", object.name.clone()));
+        }
+        if !object.obj_type.is_empty() {
+            attrs.push(("type", object.obj_type.clone()));
+        }
+        attrs.push(("x", object.x.to_string()));
+        attrs.push(("y", object.y.to_string()));
+        match object.shape {
+            ObjectShape::Rect {width, height} | ObjectShape::Ellipse {width, height} |
+            ObjectShape::Text {width, height, ..} => {
+                if width != 0.0 || height != 0.0 {
+                    attrs.push(("width", width.to_string()));
+                    attrs.push(("height", height.to_string()));
+                }
+            }
+            ObjectShape::Polyline {..} | ObjectShape::Polygon {..} | ObjectShape::Point => {}
+        }
+        if object.rotation != 0.0 {
+            attrs.push(("rotation", object.rotation.to_string()));
+        }
+        if !object.visible {
+            attrs.push(("visible", "0".to_string()));
+        }
+        self.start("object", &attrs)?;
+        self.write_properties(&object.properties)?;
+        match object.shape {
+            ObjectShape::Rect {..} => {}
+            ObjectShape::Ellipse {..} => self.element("ellipse", &[])?,
+            ObjectShape::Polyline {ref points} => self.element("polyline", &[("points", format_points(points))])?,
+            ObjectShape::Polygon {ref points} => self.element("polygon", &[("points", format_points(points))])?,
+            ObjectShape::Point => self.element("point", &[])?,
+            ObjectShape::Text {..} => self.write_text(&object.shape)?,
+        }
+        self.end()
+    }
+
+    fn write_text(&mut self, shape: &ObjectShape) -> Result<(), TiledError> {
+        if let ObjectShape::Text {ref text, ref font_family, pixel_size, wrap, colour, bold, italic, underline,
+                                  strikeout, kerning, halign, valign, ..} = *shape {
+            let mut attrs = Vec::new();
+            if font_family != "sans-serif" {
+                attrs.push(("fontfamily", font_family.clone()));
+            }
+            if pixel_size != 16 {
+                attrs.push(("pixelsize", pixel_size.to_string()));
+            }
+            let flags = [("wrap", wrap, false), ("bold", bold, false), ("italic", italic, false),
+                         ("underline", underline, false), ("strikeout", strikeout, false), ("kerning", kerning, true)];
+            for &(name, value, default) in &flags {
+                if value != default {
+                    attrs.push((name, if value { "1" } else { "0" }.to_string()));
+                }
+            }
+            if colour != (Colour {red: 0, green: 0, blue: 0, alpha: 255}) {
+                attrs.push(("color", colour.to_string()));
+            }
+            if halign != HorizontalAlignment::Left {
+                attrs.push(("halign", halign.to_string()));
+            }
+            if valign != VerticalAlignment::Top {
+                attrs.push(("valign", valign.to_string()));
+            }
+            self.start("text", &attrs)?;
+            self.characters(text)?;
+            self.end()?;
+        }
+        Ok(())
+    }
+}
+
+// Pushes the layer's id, unless it is 0 because the file had none.
+fn push_id(attrs: &mut Vec<(&str, String)>, id: u32) {
+    if id != 0 {
+        attrs.push(("id", id.to_string()));
+    }
+}
+
+// Pushes the attributes every kind of layer has, leaving out the defaults.
+fn push_common(attrs: &mut Vec<(&str, String)>, layer: LayerRef) {
+    if layer.locked() {
+        attrs.push(("locked", "1".to_string()));
+    }
+    if layer.opacity() != 1.0 {
+        attrs.push(("opacity", layer.opacity().to_string()));
+    }
+    if !layer.visible() {
+        attrs.push(("visible", "0".to_string()));
+    }
+    if let Some(colour) = layer.tint_colour() {
+        attrs.push(("tintcolor", colour.to_string()));
+    }
+    let (offset_x, offset_y) = layer.offset();
+    if offset_x != 0.0 {
+        attrs.push(("offsetx", offset_x.to_string()));
+    }
+    if offset_y != 0.0 {
+        attrs.push(("offsety", offset_y.to_string()));
+    }
+    let (parallax_x, parallax_y) = layer.parallax();
+    if parallax_x != 1.0 {
+        attrs.push(("parallaxx", parallax_x.to_string()));
+    }
+    if parallax_y != 1.0 {
+        attrs.push(("parallaxy", parallax_y.to_string()));
+    }
+}
+
+// Tiled writes -1 for a missing tile.
+fn tile_or_none(tile: Option<u32>) -> String {
+    tile.map_or("-1".to_string(), |tile| tile.to_string())
+}
+
+fn format_points(points: &[(f32, f32)]) -> String {
+    points.iter().map(|&(x, y)| format!("{},{}", x, y)).collect::<Vec<_>>().join(" ")
+}
+
+fn compress(data: &[u8], compression: DataCompression) -> Result<Vec<u8>, TiledError> {
+    let compressed = match compression {
+        DataCompression::Zlib => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(data).and_then(|_| encoder.finish())
+        }
+        DataCompression::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(data).and_then(|_| encoder.finish())
+        }
+        #[cfg(feature = "zstd")]
+        DataCompression::Zstd => ::zstd::stream::encode_all(data, 0),
+    };
+    compressed.map_err(|e| TiledError::Other(format!("Failed to compress layer data: {}", e)))
+}
+
+pub fn write_map<W: Write>(map: &Map, sink: W, options: &WriteOptions) -> Result<(), TiledError> {
+    TmxWriter::new(sink, options).write_map(map)
+}
+
+pub fn write_tileset<W: Write>(tileset: &Tileset, sink: W) -> Result<(), TiledError> {
+    TmxWriter::new(sink, &WriteOptions::default()).write_tileset_document(tileset)
+}
@@ -1,8 +1,18 @@
 extern crate tiled;
+extern crate xml;
 
 use std::path::Path;
 use std::fs::File;
-use tiled::{Map, TiledError, PropertyValue, parse, parse_file, parse_tileset};
+use std::io::Read;
+use std::sync::Arc;
+use tiled::{Map, TiledError, PropertyValue, LayerRef, LayerTile, ObjectShape, Colour,
+            HorizontalAlignment, VerticalAlignment, ObjectAlignment, Orientation, RenderOrder,
+            StaggerAxis, StaggerIndex, Grid, TileRenderSize, FillMode, WangSetType, WangTile, WangId, Autotiler,
+            WangRng, AnimationClock, Frame, PropertyTypes, Properties, parse, parse_file, parse_tileset,
+            parse_external_tileset, parse_json, parse_json_file, parse_json_tileset, write_map, write_map_file,
+            write_tileset, write_tileset_file, parse_with_reader, parse_json_with_reader,
+            FilesystemResourceReader, MemoryResourceReader, Loader,
+            DataEncoding, DataCompression, WriteOptions};
 
 fn read_from_file(p: &Path) -> Result<Map, TiledError> {
     let file = File::open(p).unwrap();
@@ -13,6 +23,10 @@ fn read_from_file_with_path(p: &Path) -> Result<Map, TiledError> {
     return parse_file(p);
 }
 
+fn read_json_from_file(p: &Path) -> Result<Map, TiledError> {
+    parse_json(File::open(p).unwrap())
+}
+
 #[test]
 fn test_gzip_and_zlib_encoded_and_raw_are_the_same() {
     let z = read_from_file(&Path::new("assets/tiled_base64_zlib.tmx")).unwrap();
@@ -38,6 +52,17 @@ fn test_just_tileset() {
     assert_eq!(r.tilesets[0], t);
 }
 
+#[test]
+fn test_embedded_tileset_errors_are_kept() {
+    let map = r#"<map version="1.0" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32">
+                  <tileset firstgid="1" name="broken" tilewidth="32" tileheight="32"><image width="32" height="32"/></tileset>
+                 </map>"#;
+    match parse(map.as_bytes()) {
+        Err(TiledError::MalformedAttributes(message)) => assert_eq!(message, "image must have a source or embedded data"),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
 #[test]
 fn test_image_layers() {
     let r = read_from_file(&Path::new("assets/tiled_image_layers.tmx")).unwrap();
@@ -52,11 +77,30 @@ fn test_image_layers() {
         assert_eq!(second.name, "Image Layer 2");
         let image = second.image.as_ref().expect(&format!("{}'s image shouldn't be None", second.name));
         assert_eq!(image.source, "tilesheet.png");
-        assert_eq!(image.width, 448);
-        assert_eq!(image.height, 192);
+        assert_eq!(image.width, Some(448));
+        assert_eq!(image.height, Some(192));
     }
 }
 
+#[test]
+fn test_image_layer_repeat_and_embedded_image() {
+    let r = read_from_file(Path::new("assets/tiled_embedded_image.tmx")).unwrap();
+    let clouds = &r.image_layers[0];
+    assert!(clouds.repeat_x && !clouds.repeat_y);
+    let image = clouds.image.as_ref().unwrap();
+    assert_eq!((image.source.as_str(), image.width, image.height), ("tilesheet.png", None, None));
+    assert_eq!(image.data, None);
+
+    let logo = &r.image_layers[1];
+    assert!(logo.repeat_x && logo.repeat_y);
+    let image = logo.image.as_ref().unwrap();
+    assert_eq!(image.source, "");
+    assert_eq!(image.format, Some("png".to_string()));
+    assert_eq!((image.width, image.height), (Some(16), Some(16)));
+    assert_eq!(image.data.as_ref().map(|data| &data[..8]), Some(&b"\x89PNG\r\n\x1a\n"[..]));
+    assert!(image.read(Path::new("assets/tiled_embedded_image.tmx"), &mut FilesystemResourceReader).is_err());
+}
+
 
 #[test]
 fn test_tile_property() {
@@ -68,3 +112,1000 @@ fn test_tile_property() {
     };
     assert_eq!("123", prop_value);
 }
+
+#[test]
+fn test_xml_encoded() {
+    let r = read_from_file(&Path::new("assets/tiled_xml.tmx")).unwrap();
+    let tiles = &r.layers[0].tiles;
+    assert_eq!(tiles.len(), 100);
+    assert!(tiles.iter().all(|row| row.len() == 100));
+    assert_eq!(tiles[0][0].gid, 30);
+    assert_eq!(tiles[0][9].gid, 30);
+    assert_eq!(tiles[0][10].gid, 0);
+    assert_eq!(tiles[1][0].gid, 44);
+    assert_eq!(tiles[4][4].gid, 73);
+    assert_eq!(tiles[6][9].gid, 74);
+    assert_eq!(tiles[99][99].gid, 0);
+}
+
+#[test]
+fn test_xml_encoded_zero_width() {
+    let map = r#"<map version="1.0" orientation="orthogonal" width="0" height="0" tilewidth="32" tileheight="32">
+                  <layer name="Empty" width="0" height="0"><data><tile gid="1"/></data></layer>
+                 </map>"#;
+    let r = parse(map.as_bytes()).unwrap();
+    assert!(r.layers[0].tiles.is_empty());
+}
+
+fn infinite_map(chunk_width: u32, encoding: &str, data: &str) -> String {
+    format!(r#"<map version="1.0" orientation="orthogonal" width="2" height="1" tilewidth="32" tileheight="32" infinite="1">
+                <layer name="ground" width="2" height="1"><data encoding="{}">
+                 <chunk x="0" y="0" width="{}" height="1">{}</chunk>
+                </data></layer>
+               </map>"#, encoding, chunk_width, data)
+}
+
+#[test]
+fn test_invalid_chunk_data() {
+    let r = parse(infinite_map(2, "csv", "1,2").as_bytes()).unwrap();
+    assert_eq!(r.layers[0].chunks[0].get_tile(1, 0).unwrap().gid, 2);
+    match parse(infinite_map(0, "base64", "AQAAAAIAAAA=").as_bytes()) {
+        Err(TiledError::MalformedAttributes(_)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+    match parse(infinite_map(2, "base64", "AQAAAAIA").as_bytes()) {
+        Err(TiledError::Other(_)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+    match parse(infinite_map(2, "csv", "1,grass").as_bytes()) {
+        Err(TiledError::Other(_)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_infinite_encodings_are_the_same() {
+    let c = read_from_file(Path::new("assets/tiled_infinite_csv.tmx")).unwrap();
+    let r = read_from_file(Path::new("assets/tiled_infinite_base64.tmx")).unwrap();
+    let z = read_from_file(Path::new("assets/tiled_infinite_base64_zlib.tmx")).unwrap();
+    let g = read_from_file(Path::new("assets/tiled_infinite_base64_gzip.tmx")).unwrap();
+    let x = read_from_file(Path::new("assets/tiled_infinite_xml.tmx")).unwrap();
+    assert!(c.infinite);
+    assert_eq!(c.layers[0].chunks.len(), 4);
+    assert!(c.layers[0].tiles.is_empty());
+    assert_eq!(c, r);
+    assert_eq!(c, z);
+    assert_eq!(c, g);
+    assert_eq!(c, x);
+}
+
+#[test]
+fn test_infinite_layer_lookup() {
+    let r = read_from_file(Path::new("assets/tiled_infinite_csv.tmx")).unwrap();
+    let layer = &r.layers[0];
+    assert_eq!(layer.bounds(), (-16, -16, 48, 32));
+    assert_eq!(layer.get_tile(-14, -16).map(|t| t.gid), Some(59));
+    assert_eq!(layer.get_tile(-15, -15).map(|t| t.gid), Some(61));
+    assert_eq!(layer.get_tile(0, -15).map(|t| t.gid), Some(16));
+    assert_eq!(layer.get_tile(18, 2).map(|t| t.gid), Some(57));
+    assert_eq!(layer.get_tile(0, 0), None);
+    assert_eq!(layer.get_tile(-17, 0), None);
+}
+
+#[test]
+fn test_finite_layer_lookup() {
+    let r = read_from_file(Path::new("assets/tiled_csv.tmx")).unwrap();
+    let layer = &r.layers[0];
+    assert!(!r.infinite);
+    assert_eq!(layer.bounds(), (0, 0, 100, 100));
+    assert_eq!(layer.get_tile(0, 0).map(|t| t.gid), Some(35));
+    assert_eq!(layer.get_tile(-1, 0), None);
+    assert_eq!(layer.get_tile(100, 0), None);
+}
+
+#[test]
+fn test_group_layers() {
+    let r = read_from_file(Path::new("assets/tiled_group_layers.tmx")).unwrap();
+    assert_eq!(r.layers.len(), 1);
+    assert_eq!(r.object_groups.len(), 1);
+    assert_eq!(r.groups.len(), 1);
+    let buildings = &r.groups[0];
+    assert_eq!(buildings.name, "Buildings");
+    assert_eq!(buildings.opacity, 0.5);
+    assert_eq!((buildings.offset_x, buildings.offset_y), (8.0, -4.0));
+    assert_eq!(buildings.properties.get("district"), Some(&PropertyValue::StringValue("harbour".to_string())));
+    assert_eq!(buildings.layers[0].name, "Walls");
+    assert_eq!(buildings.layers[0].tiles[1][1].gid, 17);
+    assert_eq!(buildings.image_layers[0].name, "Roof");
+    let interior = &buildings.groups[0];
+    assert!(!interior.visible);
+    assert_eq!(interior.object_groups[0].objects.len(), 1);
+}
+
+#[test]
+fn test_flatten_group_layers() {
+    let r = read_from_file(Path::new("assets/tiled_group_layers.tmx")).unwrap();
+    let flattened = r.flatten_layers();
+    assert_eq!(flattened.len(), 5);
+    let find = |name: &str| flattened.iter().find(|l| match l.layer {
+        LayerRef::Tile(layer) => layer.name == name,
+        LayerRef::Image(layer) => layer.name == name,
+        LayerRef::Object(group) => group.name == name,
+        LayerRef::Group(group) => group.name == name,
+    }).unwrap();
+
+    let ground = find("Ground");
+    assert_eq!((ground.opacity, ground.visible, ground.offset_x, ground.offset_y), (1.0, true, 0.0, 0.0));
+    let walls = find("Walls");
+    assert_eq!((walls.opacity, walls.visible, walls.offset_x, walls.offset_y), (0.25, true, 8.0, -4.0));
+    let roof = find("Roof");
+    assert_eq!((roof.opacity, roof.visible), (0.5, true));
+    let furniture = find("Furniture");
+    assert_eq!((furniture.opacity, furniture.visible, furniture.offset_x, furniture.offset_y), (0.5, false, 10.0, -2.0));
+
+    assert_eq!(r.groups[0].flatten_layers().len(), 3);
+}
+
+#[test]
+fn test_layer_offsets_and_parallax() {
+    let map = read_from_file(Path::new("assets/tiled_parallax.tmx")).unwrap();
+    let sky = &map.image_layers[0];
+    assert_eq!((sky.id, sky.locked, sky.offset_x, sky.offset_y), (1, true, 4.0, -2.0));
+    assert_eq!((sky.parallax_x, sky.parallax_y), (0.0, 0.0));
+    let background = &map.groups[0];
+    assert_eq!(background.tint_colour, Some(Colour {red: 255, green: 0, blue: 0, alpha: 128}));
+    let hills = &background.layers[0];
+    assert_eq!((hills.id, hills.locked, hills.offset_x, hills.offset_y), (3, false, 0.0, 6.0));
+    assert_eq!(hills.tint_colour, Some(Colour {red: 0, green: 255, blue: 0, alpha: 255}));
+    assert_eq!((hills.parallax_x, hills.parallax_y), (0.5, 1.0));
+    let birds = &background.object_groups[0];
+    assert_eq!((birds.id, birds.offset_x, birds.offset_y), (4, -3.0, 5.0));
+
+    assert_eq!("#80ff0000".parse::<Colour>().unwrap(), Colour {red: 255, green: 0, blue: 0, alpha: 128});
+    assert!("#ééé".parse::<Colour>().is_err());
+    assert!("#aéééa".parse::<Colour>().is_err());
+
+    let flattened = map.flatten_layers();
+    let names: Vec<&str> = flattened.iter().map(|l| l.layer.name()).collect();
+    assert_eq!(names, ["Sky", "Hills", "Birds", "Ground"]);
+    assert_eq!((flattened[1].parallax_x, flattened[1].parallax_y), (0.25, 0.5));
+    // Layers without parallax stay put, whatever the camera does.
+    assert_eq!(flattened[3].screen_offset(&map, 100.0, 50.0), (0.0, 0.0));
+    // The sky doesn't scroll, so it follows the camera away from the origin.
+    assert_eq!(flattened[0].screen_offset(&map, 100.0, 50.0), (72.0, 32.0));
+    assert_eq!(flattened[1].screen_offset(&map, 100.0, 50.0), (61.0, 23.0));
+    assert_eq!(flattened[2].screen_offset(&map, 32.0, 16.0), (7.0, 5.0));
+
+    let json = read_json_from_file(Path::new("assets/tiled_parallax.tmj")).unwrap();
+    assert_eq!(map, json);
+}
+
+fn class<'a>(properties: &'a Properties, name: &str) -> (&'a str, &'a Properties) {
+    match properties[name] {
+        PropertyValue::ClassValue {ref property_type, ref properties} => (property_type, properties),
+        ref other => panic!("{} is not a class property: {:?}", name, other),
+    }
+}
+
+#[test]
+fn test_class_properties() {
+    let mut map = read_from_file(Path::new("assets/tiled_class_properties.tmx")).unwrap();
+    let (class_name, boss) = class(&map.properties, "boss");
+    assert_eq!((class_name, boss.len()), ("Stats", 4));
+    assert_eq!(boss["hp"], PropertyValue::IntValue(50));
+    assert_eq!(class(boss, "weapon").0, "Weapon");
+    assert!(class(&map.properties, "minion").1.is_empty());
+    assert_eq!(map.properties["title"], PropertyValue::StringValue("Keep".to_string()));
+
+    // JSON doesn't store the types of class members, so the maps only agree
+    // once the project's classes are known.
+    let mut json = read_json_from_file(Path::new("assets/tiled_class_properties.tmj")).unwrap();
+    assert_eq!(class(&json.properties, "boss").1["speed"], PropertyValue::IntValue(2));
+    assert_ne!(map, json);
+
+    let types = PropertyTypes::from_project_file("assets/tiled_class_properties.tiled-project").unwrap();
+    assert_eq!(types.class("Weapon").unwrap()["ranged"], PropertyValue::BoolValue(false));
+    assert!(types.class("Direction").is_none());
+    types.fill_map(&mut map);
+    types.fill_map(&mut json);
+    assert_eq!(map, json);
+
+    let minion = class(&map.properties, "minion").1;
+    assert_eq!(minion.len(), 5);
+    assert_eq!(minion["hp"], PropertyValue::IntValue(10));
+    assert_eq!(minion["colour"], PropertyValue::ColorValue(0xff00ff00));
+    assert_eq!(minion["speed"], PropertyValue::FloatValue(1.5));
+    assert_eq!(class(minion, "weapon").1["damage"], PropertyValue::IntValue(1));
+    let boss = class(&map.properties, "boss").1;
+    assert_eq!(boss["speed"], PropertyValue::FloatValue(2.0));
+    assert_eq!(class(boss, "weapon").1["damage"], PropertyValue::IntValue(1));
+    assert_eq!(class(boss, "weapon").1["ranged"], PropertyValue::BoolValue(true));
+    let trap = class(&map.layers[0].properties, "trap").1;
+    assert_eq!((&trap["damage"], &trap["ranged"]), (&PropertyValue::IntValue(3), &PropertyValue::BoolValue(false)));
+
+    let mut loader = Loader::new();
+    loader.set_property_types(types);
+    assert_eq!(loader.load_json_map("assets/tiled_class_properties.tmj").unwrap(), map);
+    // Filling maps keeps their external tilesets shared.
+    let a = loader.load_tmx_map("assets/tiled_base64_external.tmx").unwrap();
+    let b = loader.load_json_map("assets/tiled_base64_external.tmj").unwrap();
+    assert!(Arc::ptr_eq(&a.tilesets[0].tileset, &loader.load_tileset("assets/tilesheet.tsx").unwrap()));
+    assert!(Arc::ptr_eq(&b.tilesets[0].tileset, &loader.load_tileset("assets/tilesheet.tsj").unwrap()));
+}
+
+fn layer_name<'a>(layer: LayerRef<'a>) -> &'a str {
+    match layer {
+        LayerRef::Tile(layer) => &layer.name,
+        LayerRef::Image(layer) => &layer.name,
+        LayerRef::Object(group) => &group.name,
+        LayerRef::Group(group) => &group.name,
+    }
+}
+
+#[test]
+fn test_layer_order() {
+    let r = read_from_file(Path::new("assets/tiled_layer_order.tmx")).unwrap();
+    let names: Vec<&str> = r.ordered_layers().into_iter().map(layer_name).collect();
+    assert_eq!(names, ["Background", "Clouds", "Foreground", "Objects", "Overlay"]);
+    assert_eq!(r.layers[1].layer_index, 2);
+    assert_eq!(r.image_layers[1].layer_index, 4);
+    assert_eq!(r.object_groups[0].layer_index, Some(3));
+}
+
+#[test]
+fn test_group_layer_order() {
+    let r = read_from_file(Path::new("assets/tiled_group_layers.tmx")).unwrap();
+    let names: Vec<&str> = r.ordered_layers().into_iter().map(layer_name).collect();
+    assert_eq!(names, ["Ground", "Buildings", "Spawns"]);
+    let names: Vec<&str> = r.groups[0].ordered_layers().into_iter().map(layer_name).collect();
+    assert_eq!(names, ["Walls", "Roof", "Interior"]);
+    let names: Vec<&str> = r.flatten_layers().into_iter().map(|l| layer_name(l.layer)).collect();
+    assert_eq!(names, ["Ground", "Walls", "Roof", "Furniture", "Spawns"]);
+}
+
+#[test]
+fn test_flipped_gids() {
+    let r = read_from_file(Path::new("assets/tiled_flipped.tmx")).unwrap();
+    let tiles = &r.layers[0].tiles;
+    assert_eq!(tiles[0][0], LayerTile {gid: 1, flip_h: true, flip_v: false, flip_d: false, rotate_hex_120: false});
+    assert_eq!(tiles[0][1], LayerTile {gid: 2, flip_h: false, flip_v: true, flip_d: false, rotate_hex_120: false});
+    assert_eq!(tiles[0][2], LayerTile {gid: 3, flip_h: false, flip_v: false, flip_d: true, rotate_hex_120: false});
+    assert_eq!(tiles[1][0], LayerTile {gid: 4, flip_h: true, flip_v: true, flip_d: false, rotate_hex_120: false});
+    assert!(tiles[1][1].is_empty());
+    assert!(tiles[1][2].rotate_hex_120);
+    assert_eq!(tiles[1][0].raw_gid(), 0xC0000004);
+
+    assert_eq!(tiles[0][0].tileset(&r).unwrap().name, "tilesheet");
+    assert_eq!(tiles[0][0].id(&r), Some(0));
+    assert_eq!(tiles[1][2].tileset(&r).unwrap().name, "tilesheet2");
+    assert_eq!(tiles[1][2].id(&r), Some(1));
+    assert_eq!(tiles[1][1].tileset(&r), None);
+    assert_eq!(r.get_tileset_by_gid(0x80000001).unwrap().name, "tilesheet");
+
+    let tile = r.object_groups[0].objects[0].tile.unwrap();
+    assert_eq!(tile.gid, 85);
+    assert!(tile.flip_h);
+    assert_eq!(tile.tileset(&r).unwrap().name, "tilesheet2");
+    assert_eq!(tile.id(&r), Some(0));
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_zstd_and_zlib_encoded_are_the_same() {
+    let z = read_from_file(Path::new("assets/tiled_base64_zlib.tmx")).unwrap();
+    let zstd = read_from_file(Path::new("assets/tiled_base64_zstd.tmx")).unwrap();
+    assert_eq!(z, zstd);
+}
+
+#[cfg(not(feature = "zstd"))]
+#[test]
+fn test_zstd_requires_feature() {
+    assert!(read_from_file(Path::new("assets/tiled_base64_zstd.tmx")).is_err());
+}
+
+#[test]
+fn test_json_and_tmx_are_the_same() {
+    let z = read_from_file(Path::new("assets/tiled_base64_zlib.tmx")).unwrap();
+    let jz = read_json_from_file(Path::new("assets/tiled_base64_zlib.tmj")).unwrap();
+    let jg = read_json_from_file(Path::new("assets/tiled_base64_gzip.tmj")).unwrap();
+    let jc = read_json_from_file(Path::new("assets/tiled_csv.tmj")).unwrap();
+    assert_eq!(z, jz);
+    assert_eq!(z, jg);
+    assert_eq!(z, jc);
+}
+
+#[test]
+fn test_json_external_tileset() {
+    let r = read_from_file(Path::new("assets/tiled_base64.tmx")).unwrap();
+    let e = parse_json_file(Path::new("assets/tiled_base64_external.tmj")).unwrap();
+    assert_eq!(r, e);
+}
+
+#[test]
+fn test_json_just_tileset() {
+    let t = parse_external_tileset(File::open(Path::new("assets/tilesheet.tsx")).unwrap()).unwrap();
+    let j = parse_json_tileset(File::open(Path::new("assets/tilesheet.tsj")).unwrap()).unwrap();
+    assert_eq!(t, j);
+}
+
+#[test]
+fn test_json_infinite() {
+    let r = read_from_file(Path::new("assets/tiled_infinite_csv.tmx")).unwrap();
+    let c = read_json_from_file(Path::new("assets/tiled_infinite_csv.tmj")).unwrap();
+    let z = read_json_from_file(Path::new("assets/tiled_infinite_base64_zlib.tmj")).unwrap();
+    assert_eq!(r, c);
+    assert_eq!(r, z);
+}
+
+fn json_base64_layer(width: u32, data: &str) -> String {
+    format!(r#"{{"version": "1.10", "orientation": "orthogonal", "width": 2, "height": 1, "tilewidth": 32, "tileheight": 32,
+                 "layers": [{{"type": "tilelayer", "name": "ground", "width": {}, "height": 1,
+                              "encoding": "base64", "data": "{}"}}]}}"#, width, data)
+}
+
+#[test]
+fn test_json_base64_zero_width() {
+    match parse_json(json_base64_layer(0, "AQAAAAIAAAA=").as_bytes()) {
+        Err(TiledError::Other(_)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_json_base64_short_data() {
+    assert_eq!(gids(&parse_json(json_base64_layer(2, "AQAAAAIAAAA=").as_bytes()).unwrap().layers[0]), vec![vec![1, 2]]);
+    match parse_json(json_base64_layer(2, "AQAAAAIA").as_bytes()) {
+        Err(TiledError::Other(_)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_json_layers() {
+    for name in &["tiled_group_layers", "tiled_image_layers"] {
+        let r = read_from_file(&Path::new("assets").join(format!("{}.tmx", name))).unwrap();
+        let j = read_json_from_file(&Path::new("assets").join(format!("{}.tmj", name))).unwrap();
+        assert_eq!(r, j, "{}.tmj differs from {}.tmx", name, name);
+    }
+}
+
+// Written as if saved in the assets directory, so that references to
+// external tilesets resolve to the same files.
+fn write_and_parse(map: &Map, options: &WriteOptions) -> Map {
+    let path = Path::new("assets/written.tmx");
+    let options = WriteOptions {map_path: Some(path.to_path_buf()), ..options.clone()};
+    let mut buffer = Vec::new();
+    write_map(map, &mut buffer, &options).unwrap();
+    parse_with_reader(&buffer[..], path, &mut FilesystemResourceReader).unwrap()
+}
+
+#[test]
+fn test_write_round_trip() {
+    let formats = [(DataEncoding::Xml, None),
+                   (DataEncoding::Csv, None),
+                   (DataEncoding::Base64, None),
+                   (DataEncoding::Base64, Some(DataCompression::Zlib)),
+                   (DataEncoding::Base64, Some(DataCompression::Gzip))];
+    let files = ["tiled_base64.tmx", "tiled_base64_gzip.tmx", "tiled_xml.tmx", "tiled_image_layers.tmx",
+                 "tiled_group_layers.tmx", "tiled_layer_order.tmx", "tiled_flipped.tmx",
+                 "tiled_infinite_csv.tmx", "tiled_base64_external.tmx", "tiled_point_text.tmx",
+                 "tiled_tile_objects.tmx", "tiled_hexagonal.tmx", "tiled_parallax.tmx",
+                 "tiled_embedded_image.tmx", "tiled_class_properties.tmx"];
+    for file in &files {
+        let map = read_from_file_with_path(&Path::new("assets").join(file)).unwrap();
+        for &(encoding, compression) in &formats {
+            let options = WriteOptions {encoding, compression, ..WriteOptions::default()};
+            assert_eq!(map, write_and_parse(&map, &options), "{} written as {:?} {:?}", file, encoding, compression);
+        }
+    }
+}
+
+#[test]
+fn test_write_external_tileset_reference() {
+    let map = read_from_file(Path::new("assets/tiled_base64.tmx")).unwrap();
+    let dir = std::env::temp_dir().join(format!("tiled_write_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy("assets/tilesheet.tsx", dir.join("tilesheet.tsx")).unwrap();
+
+    let mut options = WriteOptions::default();
+    options.external_tilesets.insert(1, "tilesheet.tsx".to_string());
+    write_map_file(&map, dir.join("map.tmx"), &options).unwrap();
+    let written = std::fs::read_to_string(dir.join("map.tmx")).unwrap();
+    let reparsed = read_from_file_with_path(&dir.join("map.tmx"));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(written.contains("<tileset firstgid=\"1\" source=\"tilesheet.tsx\" />"), "{}", written);
+    assert_eq!(map, reparsed.unwrap());
+}
+
+#[test]
+fn test_write_keeps_external_tilesets() {
+    let map = read_from_file_with_path(Path::new("assets/tiled_base64_external.tmx")).unwrap();
+    let mut buffer = Vec::new();
+    let options = WriteOptions {map_path: Some(Path::new("assets/maps/copy.tmx").to_path_buf()), ..WriteOptions::default()};
+    write_map(&map, &mut buffer, &options).unwrap();
+    let written = String::from_utf8(buffer).unwrap();
+    assert!(written.contains("<tileset firstgid=\"1\" source=\"../tilesheet.tsx\" />"), "{}", written);
+
+    let dir = std::env::temp_dir().join(format!("tiled_write_external_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    write_map_file(&map, dir.join("map.tmx"), &WriteOptions::default()).unwrap();
+    let reparsed = read_from_file_with_path(&dir.join("map.tmx"));
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(map, reparsed.unwrap());
+}
+
+// The elements, attributes and text of an XML document, ignoring formatting.
+fn xml_events(document: &[u8]) -> Vec<String> {
+    use xml::reader::{EventReader, XmlEvent};
+    EventReader::new(document).into_iter().filter_map(|e| match e.unwrap() {
+        XmlEvent::StartElement {name, attributes, ..} => {
+            let attrs: Vec<String> = attributes.iter().map(|a| format!("{}={}", a.name.local_name, a.value)).collect();
+            Some(format!("<{} {}>", name.local_name, attrs.join(" ")))
+        }
+        XmlEvent::EndElement {name} => Some(format!("</{}>", name.local_name)),
+        XmlEvent::Characters(text) => Some(text),
+        _ => None,
+    }).collect()
+}
+
+#[test]
+fn test_write_tileset_round_trip() {
+    for file in &["assets/tilesheet.tsx", "assets/tilesheet_animated.tsx", "assets/tilesheet_tall.tsx",
+                  "assets/tilesheet_collection.tsx", "assets/tilesheet_wang.tsx", "assets/tilesheet_terrain.tsx"] {
+        let original = std::fs::read(file).unwrap();
+        let tileset = parse_external_tileset(&original[..]).unwrap();
+        let mut buffer = Vec::new();
+        write_tileset(&tileset, &mut buffer).unwrap();
+        assert_eq!(xml_events(&original), xml_events(&buffer), "{}", file);
+        assert_eq!(tileset, parse_external_tileset(&buffer[..]).unwrap());
+    }
+}
+
+#[test]
+fn test_tileset_attributes() {
+    let tileset = parse_external_tileset(File::open("assets/tilesheet_tall.tsx").unwrap()).unwrap();
+    assert_eq!((tileset.tile_count(), tileset.columns()), (12, 7));
+    assert_eq!((tileset.tile_offset_x, tileset.tile_offset_y), (0, 16));
+    assert_eq!(tileset.grid, Some(Grid {orientation: Orientation::Isometric, width: 32, height: 16}));
+    assert_eq!(tileset.object_alignment, ObjectAlignment::Bottom);
+    assert_eq!(tileset.tile_render_size, TileRenderSize::Grid);
+    assert_eq!(tileset.fill_mode, FillMode::PreserveAspectFit);
+    assert_eq!(tileset.properties.get("biome"), Some(&PropertyValue::StringValue("forest".to_string())));
+    assert_eq!(tileset, parse_json_tileset(File::open("assets/tilesheet_tall.tsj").unwrap()).unwrap());
+
+    // Without `columns` and `tilecount` they are worked out from the image.
+    let r = read_from_file(Path::new("assets/tiled_base64.tmx")).unwrap();
+    assert_eq!((r.tilesets[0].tile_count(), r.tilesets[0].columns()), (84, 14));
+    assert_eq!((r.tilesets[0].tile_count, r.tilesets[0].columns), (None, None));
+    let mut buffer = Vec::new();
+    write_tileset(&r.tilesets[0], &mut buffer).unwrap();
+    let written = parse_external_tileset(&buffer[..]).unwrap();
+    assert_eq!((written.tile_count, written.columns), (None, None));
+
+    // A margin wider than the image leaves no room for tiles.
+    let tileset = r#"<tileset name="wide" tilewidth="32" tileheight="32" margin="4294967295">
+                      <image source="tilesheet.png" width="448" height="192"/>
+                     </tileset>"#;
+    let tileset = parse_external_tileset(tileset.as_bytes()).unwrap();
+    assert_eq!((tileset.tile_count(), tileset.columns()), (0, 0));
+    assert_eq!(r.tilesets[0].tile_render_size, TileRenderSize::Tile);
+    assert_eq!(r.tilesets[0].grid, None);
+}
+
+#[test]
+fn test_tile_source() {
+    let tileset = parse_external_tileset(File::open("assets/tilesheet_animated.tsx").unwrap()).unwrap();
+    let source = tileset.tile_source(3).unwrap();
+    assert_eq!(source.image.source, "animated.png");
+    assert_eq!((source.x, source.y, source.width, source.height), (34, 34, 32, 32));
+    let source = tileset.tile_source(0).unwrap();
+    assert_eq!((source.x, source.y), (1, 1));
+    assert!(tileset.tile_source(4).is_none());
+
+    let r = read_from_file(Path::new("assets/tiled_tile_objects.tmx")).unwrap();
+    let props = &r.tilesets[1];
+    let source = props.tile_source(0).unwrap();
+    assert_eq!(source.image.source, "barrel.png");
+    assert_eq!((source.x, source.y, source.width, source.height), (0, 0, 64, 48));
+    assert!(props.tile_source(1).is_none());
+}
+
+#[test]
+fn test_tile_class_and_probability() {
+    let tileset = parse_external_tileset(File::open("assets/tilesheet_wang.tsx").unwrap()).unwrap();
+    let tile = tileset.get_tile(42).unwrap();
+    assert_eq!(tile.tile_type, "grass");
+    assert_eq!(tile.probability, 0.25);
+    assert_eq!(tileset, parse_json_tileset(File::open("assets/tilesheet_wang.tsj").unwrap()).unwrap());
+
+    let tileset = parse_external_tileset(File::open("assets/tilesheet_animated.tsx").unwrap()).unwrap();
+    let tile = tileset.get_tile(0).unwrap();
+    assert_eq!(tile.tile_type, "");
+    assert_eq!(tile.probability, 1.0);
+}
+
+#[test]
+fn test_collision_shapes() {
+    let tileset = parse_external_tileset(File::open("assets/tilesheet_animated.tsx").unwrap()).unwrap();
+    let shapes = |flip_h, flip_v, flip_d| {
+        tileset.collision_shapes(0, flip_h, flip_v, flip_d).into_iter()
+            .map(|s| (s.object.id, s.x, s.y, s.rotation, s.shape))
+            .collect::<Vec<_>>()
+    };
+    let rect = |width, height| ObjectShape::Rect {width, height};
+    let polygon = |points: &[(f32, f32)]| ObjectShape::Polygon {points: points.to_vec()};
+
+    // Object 3 is turned by 90 degrees and comes out as an unrotated box.
+    assert_eq!(shapes(false, false, false), vec![
+        (1, 0.0, 16.0, 0.0, rect(32.0, 16.0)),
+        (2, 4.0, 4.0, 0.0, polygon(&[(0.0, 0.0), (8.0, 0.0), (8.0, 8.0)])),
+        (3, 20.0, 0.0, 0.0, rect(4.0, 8.0)),
+    ]);
+    assert_eq!(shapes(true, false, false), vec![
+        (1, 0.0, 16.0, 0.0, rect(32.0, 16.0)),
+        (2, 28.0, 4.0, 0.0, polygon(&[(0.0, 0.0), (-8.0, 0.0), (-8.0, 8.0)])),
+        (3, 8.0, 0.0, 0.0, rect(4.0, 8.0)),
+    ]);
+    assert_eq!(shapes(false, true, false), vec![
+        (1, 0.0, 0.0, 0.0, rect(32.0, 16.0)),
+        (2, 4.0, 28.0, 0.0, polygon(&[(0.0, 0.0), (8.0, 0.0), (8.0, -8.0)])),
+        (3, 20.0, 24.0, 0.0, rect(4.0, 8.0)),
+    ]);
+    assert_eq!(shapes(false, false, true), vec![
+        (1, 16.0, 0.0, 0.0, rect(16.0, 32.0)),
+        (2, 4.0, 4.0, 0.0, polygon(&[(0.0, 0.0), (0.0, 8.0), (8.0, 8.0)])),
+        (3, 0.0, 20.0, 0.0, rect(8.0, 4.0)),
+    ]);
+    // Rotating the tile clockwise is a diagonal and a horizontal flip.
+    assert_eq!(shapes(true, false, true)[1],
+               (2, 28.0, 4.0, 0.0, polygon(&[(0.0, 0.0), (0.0, 8.0), (-8.0, 8.0)])));
+    assert!(tileset.collision_shapes(1, false, false, false).is_empty());
+}
+
+#[test]
+fn test_tile_animation() {
+    let tileset = parse_external_tileset(File::open("assets/tilesheet_animated.tsx").unwrap()).unwrap();
+    let tile = tileset.get_tile(0).unwrap();
+    assert_eq!(tile.animation.as_ref().unwrap()[1], Frame {tile_id: 1, duration: 150});
+    assert_eq!(tile.animation_frame(0), Some(0));
+    assert_eq!(tile.animation_frame(99), Some(0));
+    assert_eq!(tile.animation_frame(100), Some(1));
+    assert_eq!(tile.animation_frame(300), Some(2));
+    // The animation lasts 350 milliseconds and then starts over.
+    assert_eq!(tile.animation_frame(350 * 3 + 120), Some(1));
+    assert_eq!(tileset.get_tile(3).unwrap().animation_frame(100), None);
+
+    let mut clock = AnimationClock::new();
+    clock.advance(260);
+    assert_eq!(clock.elapsed(), 260);
+    assert_eq!(clock.tile_id(&tileset, 0), 2);
+    assert_eq!(clock.tile_id(&tileset, 1), 1);
+    assert_eq!(clock.tile_id(&tileset, 3), 3);
+    let ids = clock.tile_ids(&tileset);
+    assert_eq!((ids.len(), ids[&0]), (1, 2));
+    clock.reset();
+    assert_eq!(clock.tile_ids(&tileset)[&0], 0);
+    assert_eq!(AnimationClock::at(100).tile_id(&tileset, 0), 1);
+}
+
+#[test]
+fn test_image_collection_tileset() {
+    let tileset = parse_external_tileset(File::open("assets/tilesheet_collection.tsx").unwrap()).unwrap();
+    assert!(tileset.is_image_collection());
+    assert_eq!((tileset.tile_count(), tileset.columns()), (3, 0));
+    assert_eq!(tileset.tile_size(0), (32, 48));
+    assert_eq!(tileset.tile_size(4), (64, 64));
+    assert_eq!(tileset.tile_size(9), (32, 24));
+
+    let source = tileset.tile_source(4).unwrap();
+    assert_eq!(source.image.source, "tree.png");
+    assert_eq!((source.x, source.y, source.width, source.height), (0, 0, 64, 64));
+    let source = tileset.tile_source(9).unwrap();
+    assert_eq!(source.image.source, "crates.png");
+    assert_eq!((source.x, source.y, source.width, source.height), (16, 8, 32, 24));
+    // Ids are sparse, so there is nothing at 1 even though it is below the
+    // tile count.
+    assert!(tileset.tile_source(1).is_none());
+
+    assert_eq!(tileset, parse_json_tileset(File::open("assets/tilesheet_collection.tsj").unwrap()).unwrap());
+}
+
+#[test]
+fn test_wang_sets() {
+    let tileset = parse_external_tileset(File::open("assets/tilesheet_wang.tsx").unwrap()).unwrap();
+    assert_eq!(tileset.wang_sets.len(), 3);
+    let ground = &tileset.wang_sets[0];
+    assert_eq!((ground.name.as_str(), ground.set_type, ground.tile), ("Ground", WangSetType::Corner, Some(0)));
+    assert_eq!(ground.properties.get("season"), Some(&PropertyValue::StringValue("summer".to_string())));
+    let sand = &ground.colours[1];
+    assert_eq!((sand.name.as_str(), sand.tile, sand.probability), ("Sand", Some(15), 0.5));
+    assert_eq!(sand.colour, Colour {red: 255, green: 255, blue: 0, alpha: 255});
+    assert_eq!(ground.colours[0].properties.get("walkable"), Some(&PropertyValue::BoolValue(true)));
+    assert_eq!(ground.wang_tiles[3], WangTile {tile_id: 3, wang_id: WangId([0, 2, 0, 2, 0, 1, 0, 1])});
+    assert_eq!(ground.wang_tiles[3].wang_id.0[WangId::TOP_RIGHT], 2);
+
+    let roads = &tileset.wang_sets[1];
+    assert_eq!((roads.set_type, roads.tile), (WangSetType::Edge, None));
+    assert_eq!(tileset.wang_sets[2].set_type, WangSetType::Mixed);
+    assert_eq!("1,1,2,2,2,2,2,1".parse::<WangId>().unwrap().to_string(), "1,1,2,2,2,2,2,1");
+    assert!("1,2,3".parse::<WangId>().is_err());
+
+    assert_eq!(tileset, parse_json_tileset(File::open("assets/tilesheet_wang.tsj").unwrap()).unwrap());
+}
+
+#[test]
+fn test_legacy_terrain() {
+    let tileset = parse_external_tileset(File::open("assets/tilesheet_terrain.tsx").unwrap()).unwrap();
+    let names: Vec<&str> = tileset.terrains.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, ["Grass", "Water"]);
+    assert_eq!((tileset.terrains[0].tile, tileset.terrains[1].tile), (Some(0), None));
+    assert_eq!(tileset.terrains[0].properties.get("walkable"), Some(&PropertyValue::BoolValue(true)));
+    assert_eq!(tileset.get_tile(0).unwrap().terrain, Some([Some(0); 4]));
+    assert_eq!(tileset.get_tile(1).unwrap().terrain, Some([Some(0), Some(0), None, Some(1)]));
+    assert_eq!(tileset, parse_json_tileset(File::open("assets/tilesheet_terrain.tsj").unwrap()).unwrap());
+}
+
+fn gids(layer: &tiled::Layer) -> Vec<Vec<u32>> {
+    layer.tiles.iter().map(|row| row.iter().map(|tile| tile.gid).collect()).collect()
+}
+
+#[test]
+fn test_autotile_corners() {
+    let mut map = read_from_file_with_path(Path::new("assets/tiled_wang.tmx")).unwrap();
+    let tileset = map.tilesets[0].clone();
+    let mut autotiler = Autotiler::new(&tileset, &tileset.wang_sets[0], 7);
+    let changed = autotiler.paint(&mut map.layers[0], 1, 1, 2);
+    assert_eq!(changed.len(), 9);
+    // Sand in the middle, with the corners touching it turned to sand in
+    // the tiles around it.
+    assert_eq!(gids(&map.layers[0]), vec![vec![3, 7, 5, 1],
+                                          vec![4, 16, 13, 1],
+                                          vec![2, 10, 9, 1],
+                                          vec![1, 1, 1, 1]]);
+    // Painting off the edge of the layer only changes what is on it.
+    let changed = autotiler.paint(&mut map.layers[0], 4, 1, 2);
+    assert_eq!(changed, vec![(3, 2), (3, 1), (3, 0)]);
+}
+
+#[test]
+fn test_autotile_edges() {
+    let mut map = read_from_file_with_path(Path::new("assets/tiled_wang.tmx")).unwrap();
+    let tileset = map.tilesets[0].clone();
+    let mut autotiler = Autotiler::new(&tileset, &tileset.wang_sets[1], 7);
+    let mut changed = autotiler.paint(&mut map.layers[1], 1, 2, 1);
+    changed.sort();
+    // Diagonal neighbours share no edges, and empty cells are left alone.
+    assert_eq!(changed, vec![(0, 2), (1, 1), (1, 2), (2, 2)]);
+    assert_eq!(gids(&map.layers[1]), vec![vec![32, 32, 32, 32],
+                                          vec![32, 28, 32, 32],
+                                          vec![30, 17, 24, 32],
+                                          vec![0, 0, 0, 0]]);
+}
+
+#[test]
+fn test_autotile_mixed_and_flips() {
+    let mut map = read_from_file_with_path(Path::new("assets/tiled_wang.tmx")).unwrap();
+    let tileset = map.tilesets[0].clone();
+    let mut autotiler = Autotiler::new(&tileset, &tileset.wang_sets[2], 7);
+    assert_eq!(autotiler.paint(&mut map.layers[0], 0, 0, 1), vec![(0, 0)]);
+    assert_eq!(map.layers[0].tiles[0][0].gid, 33);
+
+    let autotiler = Autotiler::new(&tileset, &tileset.wang_sets[0], 7);
+    let mut tile = LayerTile::new(2);
+    assert_eq!(autotiler.wang_id(tile), Some(WangId([0, 2, 0, 1, 0, 1, 0, 1])));
+    tile.flip_h = true;
+    assert_eq!(autotiler.wang_id(tile), Some(WangId([0, 1, 0, 1, 0, 1, 0, 2])));
+    // The diagonal flip comes first, moving the top right corner to the
+    // bottom left, and the horizontal flip then moves it to the bottom right.
+    tile.flip_d = true;
+    assert_eq!(autotiler.wang_id(tile), Some(WangId([0, 1, 0, 2, 0, 1, 0, 1])));
+    assert_eq!(autotiler.wang_id(LayerTile::new(0)), None);
+    assert_eq!(autotiler.wang_id(LayerTile::new(50)), None);
+}
+
+#[test]
+fn test_autotile_spreads_imperfect_matches() {
+    // There is no tile with only the left corners sand, so the closest tile
+    // for the painted cell's neighbour also turns its top right corner to
+    // sand, and the next cell has to follow. Tile 3 fits there, and has a
+    // probability of 0 so that it loses the tie for the first neighbour.
+    let map = r##"<map version="1.5" orientation="orthogonal" width="4" height="1" tilewidth="32" tileheight="32">
+                  <tileset firstgid="1" name="ground" tilewidth="32" tileheight="32" tilecount="4" columns="4">
+                   <image source="ground.png" width="128" height="32"/>
+                   <tile id="3" probability="0"/>
+                   <wangsets>
+                    <wangset name="Ground" type="corner" tile="-1">
+                     <wangcolor name="Grass" color="#00ff00" tile="-1" probability="1"/>
+                     <wangcolor name="Sand" color="#ffff00" tile="-1" probability="1"/>
+                     <wangtile tileid="0" wangid="0,1,0,1,0,1,0,1"/>
+                     <wangtile tileid="1" wangid="0,2,0,2,0,2,0,2"/>
+                     <wangtile tileid="2" wangid="0,2,0,1,0,2,0,2"/>
+                     <wangtile tileid="3" wangid="0,1,0,1,0,1,0,2"/>
+                    </wangset>
+                   </wangsets>
+                  </tileset>
+                  <layer name="Ground" width="4" height="1"><data encoding="csv">1,1,1,1</data></layer>
+                 </map>"##;
+    let mut map = parse(map.as_bytes()).unwrap();
+    let tileset = map.tilesets[0].clone();
+    let mut autotiler = Autotiler::new(&tileset, &tileset.wang_sets[0], 7);
+    assert_eq!(autotiler.paint(&mut map.layers[0], 0, 0, 2), vec![(0, 0), (1, 0), (2, 0)]);
+    assert_eq!(gids(&map.layers[0]), vec![vec![2, 3, 4, 1]]);
+    // Colours are numbered from 1, and the set only has two.
+    assert!(autotiler.paint(&mut map.layers[0], 3, 0, 0).is_empty());
+    assert!(autotiler.paint(&mut map.layers[0], 3, 0, 3).is_empty());
+    assert_eq!(gids(&map.layers[0]), vec![vec![2, 3, 4, 1]]);
+}
+
+#[test]
+fn test_autotile_ties_are_seeded() {
+    let map = read_from_file_with_path(Path::new("assets/tiled_wang.tmx")).unwrap();
+    let tileset = &map.tilesets[0];
+    // Tiles 0 and 42 are both all grass, so repainting grass picks between
+    // them at random.
+    let paint = |seed: u64| {
+        let mut layer = map.layers[0].clone();
+        Autotiler::new(tileset, &tileset.wang_sets[0], seed).paint(&mut layer, 1, 1, 1);
+        layer.tiles[1][1].gid
+    };
+    let picks: Vec<u32> = (0..32).map(paint).collect();
+    assert_eq!(picks, (0..32).map(paint).collect::<Vec<_>>());
+    assert!(picks.contains(&1) && picks.contains(&43));
+    // Tile 42 has a probability of 0.25, so it is picked less often.
+    let rare = picks.iter().filter(|&&gid| gid == 43).count();
+    assert!(rare < picks.len() - rare);
+
+    let mut rng = WangRng::new(1);
+    let mut other = WangRng::new(1);
+    assert_eq!(rng.next_u64(), other.next_u64());
+    assert!((0..100).map(|_| rng.next_f32()).all(|f| (0.0..1.0).contains(&f)));
+}
+
+#[test]
+fn test_write_tileset_file() {
+    let tileset = parse_external_tileset(File::open("assets/tilesheet_animated.tsx").unwrap()).unwrap();
+    let path = std::env::temp_dir().join(format!("tiled_tileset_test_{}.tsx", std::process::id()));
+    write_tileset_file(&tileset, &path).unwrap();
+    let written = parse_external_tileset(File::open(&path).unwrap());
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(tileset, written.unwrap());
+}
+
+#[test]
+fn test_memory_resource_reader() {
+    let mut resources = MemoryResourceReader::new();
+    resources.insert("packed/tilesheet.tsx", std::fs::read("assets/tilesheet.tsx").unwrap());
+    resources.insert("packed/tilesheet.tsj", std::fs::read("assets/tilesheet.tsj").unwrap());
+    let r = read_from_file(Path::new("assets/tiled_base64.tmx")).unwrap();
+    let e = parse_with_reader(File::open("assets/tiled_base64_external.tmx").unwrap(),
+                              "packed/map.tmx", &mut resources).unwrap();
+    let j = parse_json_with_reader(File::open("assets/tiled_base64_external.tmj").unwrap(),
+                                   "packed/map.tmj", &mut resources).unwrap();
+    assert_eq!(r, e);
+    assert_eq!(r, j);
+    assert!(parse_with_reader(File::open("assets/tiled_base64_external.tmx").unwrap(),
+                              "elsewhere/map.tmx", &mut resources).is_err());
+}
+
+#[test]
+fn test_resource_reader_relative_paths() {
+    let map = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<map version=\"1.0\" orientation=\"orthogonal\" width=\"1\" height=\"1\" tilewidth=\"32\" tileheight=\"32\">
+ <tileset firstgid=\"1\" source=\"../tilesets/./tilesheet.tsx\"/>
+</map>";
+    let mut resources = MemoryResourceReader::new();
+    resources.insert("tilesets/tilesheet.tsx", std::fs::read("assets/tilesheet.tsx").unwrap());
+    resources.insert("tilesets/tilesheet.png", vec![1, 2, 3]);
+    let map = parse_with_reader(map.as_bytes(), "maps/level.tmx", &mut resources).unwrap();
+
+    // Image sources of external tilesets are relative to the tileset file.
+    let tileset = &map.tilesets[0];
+    assert_eq!(tileset.source.as_ref().unwrap(), Path::new("tilesets/tilesheet.tsx"));
+    let mut data = Vec::new();
+    tileset.images[0].read(tileset.source.as_ref().unwrap(), &mut resources).unwrap().read_to_end(&mut data).unwrap();
+    assert_eq!(data, vec![1, 2, 3]);
+
+}
+
+#[test]
+fn test_filesystem_resource_reader_images() {
+    let map = read_from_file_with_path(Path::new("assets/tiled_base64_external.tmx")).unwrap();
+    let mut data = Vec::new();
+    map.tilesets[0].images[0].read(Path::new("assets/tiled_base64_external.tmx"), &mut FilesystemResourceReader)
+        .unwrap().read_to_end(&mut data).unwrap();
+    assert_eq!(data, std::fs::read("assets/tilesheet.png").unwrap());
+}
+
+fn external_tileset_map(first_gid: u32, source: &str) -> String {
+    format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<map version=\"1.0\" orientation=\"orthogonal\" width=\"1\" height=\"1\" tilewidth=\"32\" tileheight=\"32\">
+ <tileset firstgid=\"{}\" source=\"{}\"/>
+</map>", first_gid, source)
+}
+
+#[test]
+fn test_loader_shares_tilesets() {
+    let mut resources = MemoryResourceReader::new();
+    resources.insert("world/tilesheet.tsx", std::fs::read("assets/tilesheet.tsx").unwrap());
+    resources.insert("world/a.tmx", external_tileset_map(1, "tilesheet.tsx"));
+    resources.insert("world/caves/b.tmx", external_tileset_map(5, "../tilesheet.tsx"));
+    let mut loader = Loader::with_reader(resources);
+    let a = loader.load_tmx_map("world/a.tmx").unwrap();
+    let b = loader.load_tmx_map("world/caves/b.tmx").unwrap();
+
+    assert!(Arc::ptr_eq(&a.tilesets[0].tileset, &b.tilesets[0].tileset));
+    assert_eq!(a.tilesets[0].first_gid, 1);
+    assert_eq!(b.tilesets[0].first_gid, 5);
+    assert_eq!(b.get_tileset_by_gid(5).unwrap().name, "tilesheet");
+    assert!(b.get_tileset_by_gid(4).is_none());
+    assert_eq!(loader.tilesets().len(), 1);
+    let tileset = loader.load_tileset("world/caves/../tilesheet.tsx").unwrap();
+    assert!(Arc::ptr_eq(&a.tilesets[0].tileset, &tileset));
+}
+
+#[test]
+fn test_loader_filesystem() {
+    let mut loader = Loader::new();
+    let r = read_from_file(Path::new("assets/tiled_base64.tmx")).unwrap();
+    let e = loader.load_tmx_map("assets/tiled_base64_external.tmx").unwrap();
+    let j = loader.load_json_map("assets/tiled_base64_external.tmj").unwrap();
+    let again = loader.load_tmx_map("assets/tiled_base64_external.tmx").unwrap();
+    assert_eq!(r, e);
+    assert_eq!(r, j);
+    assert!(Arc::ptr_eq(&e.tilesets[0].tileset, &again.tilesets[0].tileset));
+    assert_eq!(loader.tilesets().len(), 2);
+}
+
+#[test]
+fn test_object_templates() {
+    let map = read_from_file_with_path(Path::new("assets/tiled_templates.tmx")).unwrap();
+    let objects = &map.object_groups[0].objects;
+
+    let chest = &objects[0];
+    assert_eq!(chest.template.as_ref().unwrap().source, Path::new("assets/chest.tx"));
+    assert_eq!(chest.name, "chest");
+    assert_eq!(chest.obj_type, "container");
+    assert_eq!((chest.x, chest.y), (64.0, 96.0));
+    assert_eq!(chest.shape, ObjectShape::Rect {width: 32.0, height: 32.0});
+    // GID 13 in the template is tile 12 of tilesheet.tsx, which the map
+    // gives GIDs from 5.
+    assert_eq!(chest.tile, Some(LayerTile::new(17)));
+    assert_eq!(chest.tile.unwrap().id(&map), Some(12));
+    assert_eq!(chest.properties.get("loot"), Some(&PropertyValue::StringValue("gold".to_string())));
+
+    let big = &objects[1];
+    assert_eq!(big.name, "big chest");
+    assert_eq!(big.shape, ObjectShape::Rect {width: 64.0, height: 64.0});
+    assert_eq!(big.properties.get("loot"), Some(&PropertyValue::StringValue("gems".to_string())));
+    assert_eq!(big.properties.get("locked"), Some(&PropertyValue::BoolValue(true)));
+    assert!(Arc::ptr_eq(chest.template.as_ref().unwrap(), big.template.as_ref().unwrap()));
+
+    let sign = &objects[2];
+    assert_eq!(sign.shape, ObjectShape::Ellipse {width: 16.0, height: 8.0});
+    assert_eq!(sign.rotation, 45.0);
+    assert_eq!(sign.tile, None);
+
+    let flipped = objects[3].tile.unwrap();
+    assert_eq!((flipped.gid, flipped.flip_h), (17, true));
+}
+
+#[test]
+fn test_json_object_templates() {
+    let t = read_from_file_with_path(Path::new("assets/tiled_templates.tmx")).unwrap();
+    let j = parse_json_file(Path::new("assets/tiled_templates.tmj")).unwrap();
+    assert_eq!(t.object_groups, j.object_groups);
+
+    let mut loader = Loader::new();
+    let tx = loader.load_template("assets/sign.tx").unwrap();
+    let tj = loader.load_template("assets/sign.tj").unwrap();
+    assert_eq!(tx.object, tj.object);
+    assert_eq!(loader.templates().len(), 2);
+}
+
+#[test]
+fn test_template_tile_outside_tileset() {
+    let template = r#"<template>
+                       <tileset firstgid="10" source="tilesheet.tsx"/>
+                       <object gid="3" width="32" height="32"/>
+                      </template>"#;
+    let map = r#"<map version="1.0" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32">
+                  <tileset firstgid="1" source="tilesheet.tsx"/>
+                  <objectgroup name="Objects"><object id="1" template="bad.tx" x="0" y="0"/></objectgroup>
+                 </map>"#;
+    let mut resources = MemoryResourceReader::new();
+    resources.insert("tilesheet.tsx", std::fs::read("assets/tilesheet.tsx").unwrap());
+    resources.insert("bad.tx", template.as_bytes().to_vec());
+    match parse_with_reader(map.as_bytes(), "map.tmx", &mut resources) {
+        Err(TiledError::Other(message)) => assert!(message.contains("outside its tileset"), "{}", message),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_map_attributes() {
+    let map = read_from_file(Path::new("assets/tiled_hexagonal.tmx")).unwrap();
+    assert_eq!(map.tiled_version, Some("1.7.2".to_string()));
+    assert_eq!(map.orientation, Orientation::Hexagonal);
+    assert_eq!(map.render_order, RenderOrder::LeftUp);
+    assert_eq!(map.compression_level, 6);
+    assert_eq!(map.hex_side_length, Some(14));
+    assert_eq!(map.stagger_axis, Some(StaggerAxis::X));
+    assert_eq!(map.stagger_index, Some(StaggerIndex::Even));
+    assert_eq!((map.parallax_origin_x, map.parallax_origin_y), (16.0, -8.0));
+    assert_eq!((map.next_layer_id, map.next_object_id), (Some(2), Some(1)));
+
+    let json = read_json_from_file(Path::new("assets/tiled_hexagonal.tmj")).unwrap();
+    assert_eq!(map, json);
+
+    let map = read_from_file(Path::new("assets/tiled_xml.tmx")).unwrap();
+    assert_eq!(map.render_order, RenderOrder::RightDown);
+    assert_eq!(map.compression_level, -1);
+    assert_eq!((map.stagger_axis, map.stagger_index, map.hex_side_length), (None, None, None));
+}
+
+#[test]
+fn test_point_and_text_objects() {
+    let map = read_from_file(Path::new("assets/tiled_point_text.tmx")).unwrap();
+    let objects = &map.object_groups[0].objects;
+    assert_eq!(objects[0].shape, ObjectShape::Point);
+    assert_eq!((objects[0].x, objects[0].y), (48.0, 80.0));
+    assert_eq!(objects[1].shape, ObjectShape::Text {
+        width: 128.0,
+        height: 40.0,
+        text: "Welcome to\nthe harbour".to_string(),
+        font_family: "Serif".to_string(),
+        pixel_size: 12,
+        wrap: true,
+        colour: Colour {red: 255, green: 0, blue: 0, alpha: 128},
+        bold: true,
+        italic: true,
+        underline: true,
+        strikeout: true,
+        kerning: false,
+        halign: HorizontalAlignment::Center,
+        valign: VerticalAlignment::Bottom,
+    });
+    match objects[2].shape {
+        ObjectShape::Text {ref text, ref font_family, pixel_size, colour, kerning, halign, valign, ..} => {
+            assert_eq!((text.as_str(), font_family.as_str(), pixel_size), ("Hello", "sans-serif", 16));
+            assert_eq!(colour, "#000000".parse().unwrap());
+            assert!(kerning);
+            assert_eq!((halign, valign), (HorizontalAlignment::Left, VerticalAlignment::Top));
+        }
+        ref shape => panic!("expected a text object, got {:?}", shape),
+    }
+
+    let json = read_json_from_file(Path::new("assets/tiled_point_text.tmj")).unwrap();
+    assert_eq!(map, json);
+}
+
+#[test]
+fn test_tile_objects() {
+    let map = read_from_file(Path::new("assets/tiled_tile_objects.tmx")).unwrap();
+    let objects = &map.object_groups[0].objects;
+
+    let flipped = &objects[0];
+    let tile = flipped.tile.unwrap();
+    assert!(tile.flip_h && !tile.flip_v);
+    assert_eq!(flipped.tileset(&map).unwrap().name, "tilesheet");
+    assert_eq!(tile.id(&map), Some(1));
+    assert_eq!(flipped.tile_data(&map).unwrap().properties.get("a tile property"),
+               Some(&PropertyValue::StringValue("123".to_string())));
+    // Anchored at the bottom left.
+    assert_eq!(flipped.bounds(&map), (32.0, 32.0, 32.0, 32.0));
+
+    // No size given, so the size comes from the tile's image. The tileset
+    // anchors its objects at their center.
+    let barrel = &objects[1];
+    assert_eq!(barrel.tileset(&map).unwrap().object_alignment, ObjectAlignment::Center);
+    assert!(barrel.tile_data(&map).is_some());
+    assert_eq!(barrel.bounds(&map), (68.0, 76.0, 64.0, 48.0));
+
+    let rect = &objects[2];
+    assert!(rect.tileset(&map).is_none());
+    assert_eq!(rect.bounds(&map), (10.0, 20.0, 30.0, 40.0));
+
+    assert_eq!(ObjectAlignment::Unspecified.origin(Orientation::Orthogonal), (0.0, 1.0));
+    assert_eq!(ObjectAlignment::Unspecified.origin(Orientation::Isometric), (0.5, 1.0));
+}